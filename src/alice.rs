@@ -3,11 +3,12 @@ use crate::{
     ecdsa,
     messages::*,
     nizk_sigma::{CompactProof, LabelledStatement, Proof, Statement, StatementKind, Witness},
+    onchain::{self, LockDescriptor, SwapChainParams},
     KeyPair, SSEcdsaTranscript,
 };
 use curv::{
     elliptic::curves::traits::{ECPoint, ECScalar},
-    BigInt, GE,
+    BigInt, FE, GE,
 };
 use merlin::Transcript;
 use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::{party_one, party_two};
@@ -17,6 +18,8 @@ pub struct AliceKeys {
     pub x_beta: KeyPair,
     pub r_beta_redeem: KeyPair,
     pub r_beta_refund: KeyPair,
+    pub r_beta_cancel: KeyPair,
+    pub r_beta_punish: KeyPair,
 }
 
 impl AliceKeys {
@@ -26,6 +29,8 @@ impl AliceKeys {
             x_beta: KeyPair::new_random(rng),
             r_beta_redeem: KeyPair::new_random(rng),
             r_beta_refund: KeyPair::new_random(rng),
+            r_beta_cancel: KeyPair::new_random(rng),
+            r_beta_punish: KeyPair::new_random(rng),
         }
     }
 }
@@ -33,6 +38,7 @@ impl AliceKeys {
 pub struct Alice1 {
     bob_commitment: Opener,
     keys: AliceKeys,
+    transcript: Transcript,
 }
 
 impl Alice1 {
@@ -62,6 +68,16 @@ impl Alice1 {
                 kind: StatementKind::Schnorr { g },
                 label: b"R_beta_refund_alice",
             },
+            Witness {
+                x: keys.r_beta_cancel.secret_key,
+                kind: StatementKind::Schnorr { g },
+                label: b"R_beta_cancel_alice",
+            },
+            Witness {
+                x: keys.r_beta_punish.secret_key,
+                kind: StatementKind::Schnorr { g },
+                label: b"R_beta_punish_alice",
+            },
             Witness {
                 x: keys.y.secret_key,
                 kind: StatementKind::DDH {
@@ -78,12 +94,38 @@ impl Alice1 {
             Alice1 {
                 bob_commitment,
                 keys,
+                transcript: transcript.clone(),
             },
             KeyGenMsg2::from(proof),
         )
     }
 
+    /// Like [`Alice1::new`], but additionally verifiably encrypts `y` to
+    /// `arbiter_key` so it can be recovered if Alice vanishes before
+    /// completing the swap. The escrow proof is bound to the same
+    /// transcript as the rest of the keygen proof, so it is checked
+    /// alongside them rather than as a separate, replayable round.
+    pub fn new_with_escrow(
+        transcript: &mut Transcript,
+        keygen_msg_1: KeyGenMsg1,
+        arbiter_key: &paillier::EncryptionKey,
+    ) -> (Alice1, KeyGenMsg2) {
+        let (mut alice1, mut keygen_msg_2) = Self::new(transcript, keygen_msg_1);
+
+        let escrow = crate::escrow::encrypt(
+            &mut alice1.transcript,
+            b"ssecdsa_keygen_escrow",
+            arbiter_key,
+            alice1.keys.y.secret_key,
+            keygen_msg_2.points.Y,
+        );
+        keygen_msg_2.escrow = Some(escrow);
+
+        (alice1, keygen_msg_2)
+    }
+
     pub fn receive_message(self, msg: KeyGenMsg3) -> Result<(Alice2, PdlMsg1), ()> {
+        let mut transcript = self.transcript;
         let bob_points = msg.commitment_opening.points.clone();
         let opening = Self::apply_labels_to_opening(msg.commitment_opening);
         self.bob_commitment
@@ -98,12 +140,17 @@ impl Alice1 {
         )
         .map_err(|_| eprintln!("Failed to verify ni_proof_correct_key"))?;
 
-        // XXX: THIS CAN PANIC IF THE FIRST ARGUMENT DOESN'T MATCH WITH THE SECOND --
-        // ARRRG
-        // HACK: ARRG STOP THE RANGE PROOF FOR NOW WHICH FAILS NON-DETERMINISTICALLY
-        // let range_proof = &msg.paillier_range_proof;
-        // party_two::PaillierPublic::verify_range_proof(&msg.N_and_c, range_proof)
-        //     .map_err(|_| eprintln!("Failed range proof "))?;
+        if !crate::range_proof::verify(
+            &mut transcript,
+            b"ssecdsa_keygen_range_proof",
+            &msg.N_and_c.ek,
+            &msg.N_and_c.encrypted_secret_share,
+            &FE::q(),
+            &msg.paillier_range_proof,
+        ) {
+            eprintln!("Failed range proof");
+            return Err(());
+        }
 
         let (pdl_first_message, pdl_challenge) = msg.N_and_c.pdl_challenge(&bob_points.X_beta);
 
@@ -168,6 +215,26 @@ impl Alice1 {
                             },
                         },
                     ),
+                    (
+                        responses.R_beta_cancel,
+                        LabelledStatement {
+                            label: b"R_beta_cancel_bob",
+                            statement: Statement::Schnorr {
+                                g,
+                                gx: points.R_beta_cancel,
+                            },
+                        },
+                    ),
+                    (
+                        responses.R_beta_punish,
+                        LabelledStatement {
+                            label: b"R_beta_punish_bob",
+                            statement: Statement::Schnorr {
+                                g,
+                                gx: points.R_beta_punish,
+                            },
+                        },
+                    ),
                 ],
             },
         }
@@ -209,9 +276,22 @@ pub struct Alice3 {
 }
 
 impl Alice3 {
-    pub fn receive_message(self, msg: PdlMsg4) -> Result<(Alice4, SignMsg1), ()> {
+    /// `chain` fixes the swap's on-chain leg -- the lock output `X_beta`
+    /// ended up funded at, and who the redeem/refund/punish outputs pay --
+    /// agreed with Bob out of band now that keygen has produced `X_beta`
+    /// and the lock output can actually be funded.
+    pub fn receive_message(
+        self,
+        msg: PdlMsg4,
+        chain: SwapChainParams,
+    ) -> Result<(Alice4, SignMsg1), ()> {
         party_two::PaillierPublic::verify_pdl(&self.pdl_challenge, &self.pdl_first_message, &msg)?;
 
+        let lock = LockDescriptor {
+            combined_key: onchain::to_bitcoin_pubkey(&self.X_beta),
+        };
+        let txs = onchain::swap_transactions(&chain, &lock);
+
         let (c_beta_redeem_missing_y_and_bob_R, R_beta_redeem) = {
             // FIXME: Remove this by rewriting party_two::PartialSig::compute
             // We contrive the nonce point that makes compute() dooes the thing we want
@@ -223,7 +303,7 @@ impl Alice3 {
                 &party_two::Party2Private::set_private_key(&self.keys.x_beta.into()),
                 &self.keys.r_beta_redeem.into(),
                 &R_contrived,
-                &BigInt::from(&beta_redeem_tx()[..]),
+                &BigInt::from(&txs.redeem_sighash[..]),
             )
             .c3;
 
@@ -240,7 +320,27 @@ impl Alice3 {
             &party_two::Party2Private::set_private_key(&self.keys.x_beta.into()),
             &self.keys.r_beta_refund.into(),
             &self.bob_points.R_beta_refund,
-            &BigInt::from(&beta_refund_tx()[..]),
+            &BigInt::from(&txs.refund_sighash[..]),
+        )
+        .c3;
+
+        let c_beta_cancel_missing_bob_R = party_two::PartialSig::compute(
+            &self.N_and_c.ek,
+            &self.N_and_c.encrypted_secret_share,
+            &party_two::Party2Private::set_private_key(&self.keys.x_beta.into()),
+            &self.keys.r_beta_cancel.into(),
+            &self.bob_points.R_beta_cancel,
+            &BigInt::from(&txs.cancel_sighash[..]),
+        )
+        .c3;
+
+        let c_beta_punish_missing_bob_R = party_two::PartialSig::compute(
+            &self.N_and_c.ek,
+            &self.N_and_c.encrypted_secret_share,
+            &party_two::Party2Private::set_private_key(&self.keys.x_beta.into()),
+            &self.keys.r_beta_punish.into(),
+            &self.bob_points.R_beta_punish,
+            &BigInt::from(&txs.punish_sighash[..]),
         )
         .c3;
 
@@ -249,10 +349,13 @@ impl Alice3 {
                 keys: self.keys,
                 X_beta: self.X_beta,
                 R_beta_redeem,
+                redeem_sighash: txs.redeem_sighash,
             },
             SignMsg1 {
                 c_beta_redeem_missing_y_and_bob_R,
                 c_beta_refund_missing_bob_R,
+                c_beta_cancel_missing_bob_R,
+                c_beta_punish_missing_bob_R,
             },
         ))
     }
@@ -262,13 +365,14 @@ pub struct Alice4 {
     keys: AliceKeys,
     R_beta_redeem: GE,
     X_beta: GE,
+    redeem_sighash: secp256k1::Message,
 }
 
 impl Alice4 {
     pub fn receive_message(self, msg: SignMsg2) -> Result<((), BlockchainMsg), ()> {
         let s_beta_redeem = msg.s_beta_redeem_missing_y * self.keys.y.secret_key.invert();
         let sig_beta_redeem = ecdsa::normalize_and_verify(
-            &beta_redeem_tx(),
+            &self.redeem_sighash,
             &self.X_beta,
             &s_beta_redeem,
             &self.R_beta_redeem,
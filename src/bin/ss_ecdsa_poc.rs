@@ -1,69 +1,178 @@
 #![allow(non_snake_case)]
+use bitcoin::{Address, Network, Txid};
+use bitcoin_hashes::Hash;
+use curv::{
+    elliptic::curves::traits::{ECPoint, ECScalar},
+    BigInt, FE, GE,
+};
 use merlin::Transcript;
-use ss_ecdsa_poc::{alice::Alice1, bob::Bob1};
+use paillier::{traits::KeyGeneration, Paillier};
+use secp256k1::Message;
+use ss_ecdsa_poc::{
+    alice::Alice1,
+    bob::Bob1,
+    gg18::{Gg18Alice1, Gg18Bob1, Gg18CounterpartyKeyShare, Gg18KeyShare, SigningBackend},
+    onchain::{RelativeTimelock, SwapChainParams},
+};
+
+/// A funded lock output, standing in for the real funding transaction a
+/// wallet would broadcast out of band once keygen has produced `X_beta`.
+fn example_chain_params() -> SwapChainParams {
+    let example_address = || {
+        let point = GE::generator() * FE::new_random();
+        Address::p2wpkh(
+            &bitcoin::PublicKey::from_slice(&point.get_element().serialize()[..]).unwrap(),
+            Network::Bitcoin,
+        )
+        .unwrap()
+    };
+
+    SwapChainParams {
+        lock_txid: Txid::from_slice(&[7u8; 32]).unwrap(),
+        lock_vout: 0,
+        lock_value: 1_000_000_000,
+        alice_redeem_address: example_address(),
+        bob_refund_address: example_address(),
+        bob_punish_address: example_address(),
+        cancel_timelock: RelativeTimelock(144),
+        punish_timelock: RelativeTimelock(144),
+    }
+}
 
 pub fn main() -> Result<(), ()> {
-    {
-        // KEY GENERATION
-        // Y is the public key Bob wants to know the private key for
-        let mut alice_transcript = Transcript::new(b"ss_ecdsa");
-        let mut bob_transcript = Transcript::new(b"ss_ecdsa");
-
-        let (bob, keygen_msg_1) = Bob1::new(&mut bob_transcript);
-        println!("[BOB => ALICE] commitment to points and proofs",);
-        let (alice, keygen_msg_2) = Alice1::new(&mut alice_transcript, keygen_msg_1);
-        println!("[ALICE => BOB] points and proofs");
-        let (bob, keygen_msg_3) = bob.receive_message(&mut bob_transcript, keygen_msg_2)?;
-        println!("[BOB => ALICE] Opens commitment and sends encrypted keys");
-        let (alice, pdl_msg_1) = alice.receive_message(keygen_msg_3)?;
-        println!("[ALICE => BOB] PDL challenge");
-        let (bob, pdl_msg_2) = bob.receive_message(pdl_msg_1)?;
-        println!("[BOB => Alice] PDL commited response");
-        let (alice, pdl_msg_3) = alice.receive_message(pdl_msg_2);
-        println!("[Alice => Bob] PDL reveal challenge");
-        let (bob, pdl_msg_4) = bob.receive_message(pdl_msg_3)?;
-        println!("[Bob => Alice] PDL open commited response");
-        let (alice, sign_msg_1) = alice.receive_message(pdl_msg_4)?;
-        println!("[Alice => Bob] Encrypted partial signatures");
-        let (bob, sign_msg_2) = bob.receive_message(sign_msg_1)?;
-        println!(
-            "[Bob => Alice] Conditional beta redeem signature + complete beta refund signature"
-        );
-        let (_, blockchain_msg) = alice.receive_message(sign_msg_2)?;
-        println!("[ALICE => BLOCKCHAIN] beta_redeem_tx (i.e broadcasts beta redeem transaction)");
-        let (..) = bob.receive_message(blockchain_msg)?;
-
-        // // println!("[ALICE => BOB] the lock {:?}", Y);
-
-        // // println!("[BOB => ALICE] His public key Comm(X₁, nizk(X₁))");
-
-        // println!("[ALICE => BOB] X₂, nizk(X₂)");
-
-        // println!("[BOB => ALICE] Opens his commitment, sends c = PaillierEncrypt(x₁),
-        // N  and proofs for N"); let (alice, pdl_msg_2) =
-        // alice.receive_message(pdl_msg_1)?; println!("[ALICE => BOB] PDL:
-        // challenge c′"); let (bob, keygen_msg_5) =
-        // bob.receive_message(keygen_msg_4)?; println!("[BOB => ALICE] PDL:
-        // Comm(Q̂)"); let (alice, keygen_msg_6) =
-        // alice.receive_message(keygen_msg_5); println!("[ALICE => BOB] PDL:
-        // Reveal a,b used to produce c′"); let (bob, keygen_msg_7) =
-        // bob.receive_message(keygen_msg_6)?; println!("[BOB => ALICE] PDL:
-        // Opens commitment to Q̂"); let (alice, sign_msg_1) =
-        // alice.receive_message(keygen_msg_7)?;
-
-        // // Nonce Generation
-        // println!("[ALICE => BOB] Comm(R₂, nizk(R₂))");
-        // let (bob, sign_msg_2) = bob.receive_message(sign_msg_1);
-        // println!("[BOΒ => ALICE] R₁, nizk(R₁)");
-        // let (alice, sign_msg_3) = alice.receive_message(sign_msg_2)?;
-        // println!("[BOΒ => ALICE] R₁, nizk(R₁), R₃, c₃");
-        // let (bob, sign_msg_4) = bob.receive_message(sign_msg_3)?;
-        // println!("[BOΒ => ALICE] s′′");
-        // let (_alice, blockchain_msg) = alice.receive_message(sign_msg_4)?;
-        // println!("[ALICE => BLOCKCHAIN] s (i.e broadcasts the signed transaction)");
-        // let (bob, _) = bob.receive_message(blockchain_msg)?;
-        // println!("BOΒ learns {:?}", bob.y);
+    run(SigningBackend::Lindell2017)
+}
+
+fn run(backend: SigningBackend) -> Result<(), ()> {
+    match backend {
+        SigningBackend::Lindell2017 => run_lindell_2017(),
+        SigningBackend::Gg18Mta => run_gg18_mta(),
+    }
+}
+
+fn run_lindell_2017() -> Result<(), ()> {
+    // KEY GENERATION
+    // Y is the public key Bob wants to know the private key for
+    let mut alice_transcript = Transcript::new(b"ss_ecdsa");
+    let mut bob_transcript = Transcript::new(b"ss_ecdsa");
+
+    let (bob, keygen_msg_1) = Bob1::new(&mut bob_transcript);
+    println!("[BOB => ALICE] commitment to points and proofs",);
+    let (alice, keygen_msg_2) = Alice1::new(&mut alice_transcript, keygen_msg_1);
+    println!("[ALICE => BOB] points and proofs");
+    let (bob, keygen_msg_3) = bob.receive_message(&mut bob_transcript, keygen_msg_2)?;
+    println!("[BOB => ALICE] Opens commitment and sends encrypted keys");
+    let (alice, pdl_msg_1) = alice.receive_message(keygen_msg_3)?;
+    println!("[ALICE => BOB] PDL challenge");
+    let (bob, pdl_msg_2) = bob.receive_message(pdl_msg_1)?;
+    println!("[BOB => Alice] PDL commited response");
+    let (alice, pdl_msg_3) = alice.receive_message(pdl_msg_2);
+    println!("[Alice => Bob] PDL reveal challenge");
+    let (bob, pdl_msg_4) = bob.receive_message(pdl_msg_3)?;
+    println!("[Bob => Alice] PDL open commited response");
+    let chain = example_chain_params();
+    let (alice, sign_msg_1) = alice.receive_message(pdl_msg_4, chain.clone())?;
+    println!("[Alice => Bob] Encrypted partial signatures");
+    let (bob, sign_msg_2) = bob.receive_message(sign_msg_1, chain)?;
+    println!("[Bob => Alice] Conditional beta redeem signature");
+    let (_, blockchain_msg) = alice.receive_message(sign_msg_2)?;
+    println!("[ALICE => BLOCKCHAIN] the real beta redeem transaction's sighash, signed");
+    let (..) = bob.receive_message(blockchain_msg)?;
+
+    // // println!("[ALICE => BOB] the lock {:?}", Y);
+
+    // // println!("[BOB => ALICE] His public key Comm(X₁, nizk(X₁))");
+
+    // println!("[ALICE => BOB] X₂, nizk(X₂)");
+
+    // println!("[BOB => ALICE] Opens his commitment, sends c = PaillierEncrypt(x₁),
+    // N  and proofs for N"); let (alice, pdl_msg_2) =
+    // alice.receive_message(pdl_msg_1)?; println!("[ALICE => BOB] PDL:
+    // challenge c′"); let (bob, keygen_msg_5) =
+    // bob.receive_message(keygen_msg_4)?; println!("[BOB => ALICE] PDL:
+    // Comm(Q̂)"); let (alice, keygen_msg_6) =
+    // alice.receive_message(keygen_msg_5); println!("[ALICE => BOB] PDL:
+    // Reveal a,b used to produce c′"); let (bob, keygen_msg_7) =
+    // bob.receive_message(keygen_msg_6)?; println!("[BOB => ALICE] PDL:
+    // Opens commitment to Q̂"); let (alice, sign_msg_1) =
+    // alice.receive_message(keygen_msg_7)?;
+
+    // // Nonce Generation
+    // println!("[ALICE => BOB] Comm(R₂, nizk(R₂))");
+    // let (bob, sign_msg_2) = bob.receive_message(sign_msg_1);
+    // println!("[BOΒ => ALICE] R₁, nizk(R₁)");
+    // let (alice, sign_msg_3) = alice.receive_message(sign_msg_2)?;
+    // println!("[BOΒ => ALICE] R₁, nizk(R₁), R₃, c₃");
+    // let (bob, sign_msg_4) = bob.receive_message(sign_msg_3)?;
+    // println!("[BOΒ => ALICE] s′′");
+    // let (_alice, blockchain_msg) = alice.receive_message(sign_msg_4)?;
+    // println!("[ALICE => BLOCKCHAIN] s (i.e broadcasts the signed transaction)");
+    // let (bob, _) = bob.receive_message(blockchain_msg)?;
+    // println!("BOΒ learns {:?}", bob.y);
+
+    Ok(())
+}
+
+/// Same ceremony as [`run_lindell_2017`], but with the two-party signature
+/// formed via the GG18 MtA backend instead of Paillier decryption. Key
+/// shares are split by a trusted dealer here since this module only covers
+/// signing, not GG18's own (different) keygen.
+fn run_gg18_mta() -> Result<(), ()> {
+    let x_bob = FE::new_random();
+    let x_alice = FE::new_random();
+    let X_bob = GE::generator() * x_bob;
+    let X_alice = GE::generator() * x_alice;
+
+    let (ek_bob, dk_bob) = Paillier::keypair().keys();
+    let (ek_alice, dk_alice) = Paillier::keypair().keys();
+
+    let bob_key_share = Gg18KeyShare {
+        x_i: x_bob,
+        X: X_bob,
+        ek: ek_bob.clone(),
+        dk: dk_bob,
+    };
+    let alice_counterparty = Gg18CounterpartyKeyShare { X: X_bob, ek: ek_bob };
+    let alice_key_share = Gg18KeyShare {
+        x_i: x_alice,
+        X: X_alice,
+        ek: ek_alice.clone(),
+        dk: dk_alice,
     };
+    let bob_counterparty = Gg18CounterpartyKeyShare { X: X_alice, ek: ek_alice };
+
+    let msg = Message::from_slice(&[7u8; 32]).unwrap();
+    let y_lock: FE = ECScalar::from(&BigInt::from(1));
+
+    let mut bob_transcript = Transcript::new(b"gg18_sign");
+    let mut alice_transcript = Transcript::new(b"gg18_sign");
+
+    let (bob, sign_msg_1) = Gg18Bob1::new(
+        bob_key_share,
+        bob_counterparty,
+        msg,
+        y_lock,
+        &mut bob_transcript,
+    );
+    println!("[BOB => ALICE] Gamma commitment, encrypted nonce share, range proof");
+    let alice = Gg18Alice1::new(
+        alice_key_share,
+        alice_counterparty,
+        msg,
+        y_lock,
+        &mut alice_transcript,
+    );
+    let (alice, sign_msg_2) = alice.receive_message(sign_msg_1)?;
+    println!("[ALICE => BOB] Gamma commitment, encrypted nonce share, range proof, MtA responses");
+    let (bob, sign_msg_3) = bob.receive_message(sign_msg_2)?;
+    println!("[BOB => ALICE] MtA responses, delta share");
+    let sign_msg_4 = alice.receive_message(sign_msg_3);
+    println!("[ALICE => BOB] delta share, partial signature");
+    let signature = bob.receive_message(sign_msg_4)?;
+    println!(
+        "[BOB] combined and verified signature, rx = {:?}",
+        signature.Rx
+    );
 
     Ok(())
 }
@@ -3,13 +3,13 @@ use crate::{
     ecdsa,
     messages::*,
     nizk_sigma_proof::{CompactProof, Proof, StatementKind, Witness},
+    onchain::{self, LockDescriptor, SwapChainParams},
     KeyPair, SSEcdsaTranscript,
 };
 use ecdsa::Signature;
 
 use curv::{
-    arithmetic::traits::Modulo,
-    elliptic::curves::traits::{ECPoint, ECScalar},
+    elliptic::curves::traits::ECScalar,
     BigInt, FE, GE,
 };
 use merlin::Transcript;
@@ -20,6 +20,8 @@ pub struct BobKeys {
     pub x_alpha: KeyPair,
     pub r_beta_redeem: KeyPair,
     pub r_beta_refund: KeyPair,
+    pub r_beta_cancel: KeyPair,
+    pub r_beta_punish: KeyPair,
 }
 
 impl BobKeys {
@@ -29,6 +31,8 @@ impl BobKeys {
             x_beta: KeyPair::new_random(rng),
             r_beta_redeem: KeyPair::new_random(rng),
             r_beta_refund: KeyPair::new_random(rng),
+            r_beta_cancel: KeyPair::new_random(rng),
+            r_beta_punish: KeyPair::new_random(rng),
         }
     }
 }
@@ -65,6 +69,16 @@ impl Bob1 {
                 kind: StatementKind::Schnorr { g },
                 label: b"R_beta_refund_bob",
             },
+            Witness {
+                x: keys.r_beta_cancel.secret_key,
+                kind: StatementKind::Schnorr { g },
+                label: b"R_beta_cancel_bob",
+            },
+            Witness {
+                x: keys.r_beta_punish.secret_key,
+                kind: StatementKind::Schnorr { g },
+                label: b"R_beta_punish_bob",
+            },
         ];
 
         let (commitment, commitment_opening) =
@@ -83,8 +97,30 @@ impl Bob1 {
         self,
         transcript: &mut Transcript,
         alice_keygen: KeyGenMsg2,
+    ) -> Result<(Bob2, KeyGenMsg3), ()> {
+        self.receive_message_inner(transcript, alice_keygen, None)
+    }
+
+    /// Like [`Bob1::receive_message`], but additionally checks that Alice
+    /// escrowed `y` to `arbiter_key` (see `Alice1::new_with_escrow`),
+    /// rejecting the round if she didn't.
+    pub fn receive_message_with_escrow(
+        self,
+        transcript: &mut Transcript,
+        alice_keygen: KeyGenMsg2,
+        arbiter_key: &paillier::EncryptionKey,
+    ) -> Result<(Bob2, KeyGenMsg3), ()> {
+        self.receive_message_inner(transcript, alice_keygen, Some(arbiter_key))
+    }
+
+    fn receive_message_inner(
+        self,
+        transcript: &mut Transcript,
+        alice_keygen: KeyGenMsg2,
+        arbiter_key: Option<&paillier::EncryptionKey>,
     ) -> Result<(Bob2, KeyGenMsg3), ()> {
         let alice_points = alice_keygen.points.clone();
+        let escrow = alice_keygen.escrow.clone();
         let alice_proof = CompactProof::from(alice_keygen);
 
         if !alice_proof.verify(transcript, b"ssecdsa_keygen_alice") {
@@ -92,13 +128,34 @@ impl Bob1 {
             return Err(());
         }
 
+        if let Some(arbiter_key) = arbiter_key {
+            let escrow = escrow
+                .ok_or(())
+                .map_err(|_| eprintln!("Expected Alice to escrow y but she didn't"))?;
+            if !crate::escrow::verify(
+                transcript,
+                b"ssecdsa_keygen_escrow",
+                arbiter_key,
+                alice_points.Y,
+                &escrow,
+            ) {
+                eprintln!("Failed to verify escrow of y");
+                return Err(());
+            }
+        }
+
         let pq_and_c = party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(
             &self.keys.x_beta.into(),
         );
 
-        let range_proof = party_one::PaillierKeyPair::generate_range_proof(
-            &pq_and_c,
-            &party_one::Party1Private::set_private_key(&self.keys.x_beta.into(), &pq_and_c),
+        let range_proof = crate::range_proof::prove(
+            transcript,
+            b"ssecdsa_keygen_range_proof",
+            &pq_and_c.ek,
+            &pq_and_c.encrypted_share,
+            &pq_and_c.randomness,
+            &self.keys.x_beta.secret_key.to_big_int(),
+            &FE::q(),
         );
 
         let paillier_correct_key_proof =
@@ -200,7 +257,20 @@ pub struct Bob4 {
 use paillier::{traits::Decrypt, DecryptionKey, Paillier, RawCiphertext, RawPlaintext};
 
 impl Bob4 {
-    pub fn receive_message(self, msg: SignMsg1) -> Result<(Bob5, SignMsg2), ()> {
+    /// `chain` fixes the swap's on-chain leg -- the lock output `X_beta`
+    /// ended up funded at, and who the redeem/refund/punish outputs pay --
+    /// agreed with Alice out of band now that keygen has produced `X_beta`
+    /// and the lock output can actually be funded.
+    pub fn receive_message(
+        self,
+        msg: SignMsg1,
+        chain: SwapChainParams,
+    ) -> Result<(Bob5, SignMsg2), ()> {
+        let lock = LockDescriptor {
+            combined_key: onchain::to_bitcoin_pubkey(&self.X_beta),
+        };
+        let txs = onchain::swap_transactions(&chain, &lock);
+
         let s_beta_redeem_missing_y = {
             let R_beta_redeem = self.alice_points.R3 * self.keys.r_beta_redeem.secret_key;
             let s_tag = Self::extract_partial_sig(
@@ -209,7 +279,7 @@ impl Bob4 {
                 self.X_beta,
                 R_beta_redeem,
                 self.alice_points.R_beta_redeem,
-                beta_redeem_tx(),
+                txs.redeem_sighash,
             )
             .map_err(|_| eprintln!("beta redeem verify failed"))?;
             s_tag * self.keys.r_beta_redeem.secret_key.invert()
@@ -224,24 +294,69 @@ impl Bob4 {
                 self.X_beta,
                 R_beta_refund,
                 self.alice_points.R_beta_refund,
-                beta_refund_tx(),
+                txs.refund_sighash,
             )
             .map_err(|_| eprintln!("beta refund verify failed"))?;
             let s_beta_refund = s_tag * self.keys.r_beta_refund.secret_key.invert();
             ecdsa::normalize_and_verify(
-                &beta_refund_tx(),
+                &txs.refund_sighash,
                 &self.X_beta,
                 &s_beta_refund,
                 &R_beta_refund,
             )?
         };
 
+        let sig_beta_cancel = {
+            let R_beta_cancel =
+                self.alice_points.R_beta_cancel * &self.keys.r_beta_cancel.secret_key;
+            let s_tag = Self::extract_partial_sig(
+                &self.pq_and_c.dk,
+                &msg.c_beta_cancel_missing_bob_R,
+                self.X_beta,
+                R_beta_cancel,
+                self.alice_points.R_beta_cancel,
+                txs.cancel_sighash,
+            )
+            .map_err(|_| eprintln!("beta cancel verify failed"))?;
+            let s_beta_cancel = s_tag * self.keys.r_beta_cancel.secret_key.invert();
+            ecdsa::normalize_and_verify(
+                &txs.cancel_sighash,
+                &self.X_beta,
+                &s_beta_cancel,
+                &R_beta_cancel,
+            )?
+        };
+
+        let sig_beta_punish = {
+            let R_beta_punish =
+                self.alice_points.R_beta_punish * &self.keys.r_beta_punish.secret_key;
+            let s_tag = Self::extract_partial_sig(
+                &self.pq_and_c.dk,
+                &msg.c_beta_punish_missing_bob_R,
+                self.X_beta,
+                R_beta_punish,
+                self.alice_points.R_beta_punish,
+                txs.punish_sighash,
+            )
+            .map_err(|_| eprintln!("beta punish verify failed"))?;
+            let s_beta_punish = s_tag * self.keys.r_beta_punish.secret_key.invert();
+            ecdsa::normalize_and_verify(
+                &txs.punish_sighash,
+                &self.X_beta,
+                &s_beta_punish,
+                &R_beta_punish,
+            )?
+        };
+
         Ok((
             Bob5 {
                 X_beta: self.X_beta,
                 s_beta_redeem_missing_y,
                 Y: self.alice_points.Y,
+                redeem_sighash: txs.redeem_sighash,
                 sig_beta_refund,
+                sig_beta_cancel,
+                sig_beta_punish,
             },
             SignMsg2 {
                 s_beta_redeem_missing_y,
@@ -259,12 +374,17 @@ impl Bob4 {
     ) -> Result<FE, ()> {
         let tmp: RawPlaintext = Paillier::decrypt(paillier_key, &RawCiphertext::from(c.clone()));
         let s_tag: FE = ECScalar::from(&tmp.0);
-        let g = GE::generator();
-        let rx: FE = ECScalar::from(&R.x_coor().unwrap());
-        let m: FE = ECScalar::from(&BigInt::from(&msg[..]));
 
-        // Check that alice didn't send us an invalid s_tag
-        if R_partial * s_tag == X * rx + g * m {
+        // Check that alice didn't send us an invalid s_tag. The keygen
+        // round's `Y`/`R3` proof already ties these nonces to `Y`, so this
+        // is the generic adaptor equation with no extra proof of its own --
+        // shared with the redeem direction below via the same `PreSignature`.
+        let pre_signature = ecdsa::PreSignature {
+            R: R_partial,
+            R_encrypted: R,
+            s_hat: s_tag,
+        };
+        if pre_signature.verify_encrypted(&X, &msg) {
             Ok(s_tag)
         } else {
             Err(())
@@ -276,14 +396,17 @@ pub struct Bob5 {
     X_beta: GE,
     s_beta_redeem_missing_y: FE,
     Y: GE,
+    redeem_sighash: secp256k1::Message,
     #[allow(dead_code)]
     sig_beta_refund: Signature,
+    sig_beta_cancel: Signature,
+    sig_beta_punish: Signature,
 }
 
 impl Bob5 {
     pub fn receive_message(self, msg: BlockchainMsg) -> Result<(Bob7, ()), ()> {
         if !ecdsa::verify(
-            &beta_redeem_tx(),
+            &self.redeem_sighash,
             &msg.sig_beta_redeem.Rx,
             &msg.sig_beta_redeem.s,
             &self.X_beta,
@@ -293,35 +416,73 @@ impl Bob5 {
 
         Ok((
             Bob7 {
-                y: self.extract_y(msg.sig_beta_redeem.s)?,
+                y: ecdsa::recover_y(&self.Y, &self.s_beta_redeem_missing_y, &msg.sig_beta_redeem)
+                    .ok_or(())?,
             },
             (),
         ))
     }
 
-    fn extract_y(&self, s: FE) -> Result<FE, ()> {
-        let q = FE::q();
-        let y_maybe = s.invert() * self.s_beta_redeem_missing_y;
-        let Y_maybe: GE = GE::generator() * y_maybe;
-        let Y = &self.Y;
-
-        // NOTE: There may be faster ways of checking this
-        if Y_maybe.x_coor().unwrap() == Y.x_coor().unwrap() {
-            if Y_maybe.y_coor().unwrap() != Y.y_coor().unwrap() {
-                Ok(ECScalar::from(&BigInt::mod_sub(
-                    &q,
-                    &y_maybe.to_big_int(),
-                    &q,
-                )))
-            } else {
-                Ok(y_maybe)
-            }
-        } else {
-            Err(())
+    /// The signature that moves the funds from the lock output into the
+    /// cancel output (still keyed to `X_beta`), which Bob can then `punish`
+    /// once its relative timelock expires. Broadcast this if Alice vanishes
+    /// before `beta_redeem`. No redeem transaction spends the cancel output.
+    pub fn cancel(&self) -> &Signature {
+        &self.sig_beta_cancel
+    }
+
+    /// Call once `sig_beta_cancel` has confirmed on-chain, to move to
+    /// [`Bob6`], which holds the `punish` signature for the cancel output.
+    pub fn observe_cancel_confirmed(self) -> Bob6 {
+        Bob6 {
+            X_beta: self.X_beta,
+            s_beta_redeem_missing_y: self.s_beta_redeem_missing_y,
+            Y: self.Y,
+            redeem_sighash: self.redeem_sighash,
+            sig_beta_punish: self.sig_beta_punish,
         }
     }
 }
 
+/// Bob has broadcast `cancel` and waits out its relative timelock, after
+/// which he can unilaterally `punish`. [`Bob6::receive_message`] extracts `y`
+/// from a redeem signature exactly as [`Bob5`] does; that redeem transaction
+/// spends the lock output, not the cancel output.
+pub struct Bob6 {
+    X_beta: GE,
+    s_beta_redeem_missing_y: FE,
+    Y: GE,
+    redeem_sighash: secp256k1::Message,
+    sig_beta_punish: Signature,
+}
+
+impl Bob6 {
+    /// The signature that claims the cancel output for Bob once its
+    /// relative timelock has expired.
+    pub fn punish(&self) -> &Signature {
+        &self.sig_beta_punish
+    }
+
+    pub fn receive_message(self, msg: BlockchainMsg) -> Result<(Bob7, ()), ()> {
+        if !ecdsa::verify(
+            &self.redeem_sighash,
+            &msg.sig_beta_redeem.Rx,
+            &msg.sig_beta_redeem.s,
+            &self.X_beta,
+        ) {
+            return Err(());
+        }
+
+        Ok((
+            Bob7 {
+                y: ecdsa::recover_y(&self.Y, &self.s_beta_redeem_missing_y, &msg.sig_beta_redeem)
+                    .ok_or(())?,
+            },
+            (),
+        ))
+    }
+}
+
 pub struct Bob7 {
     pub y: FE,
 }
@@ -2,10 +2,10 @@ use crate::nizk_sigma_proof::{GenRngFromWitness, Proof, Witness};
 use merlin::Transcript;
 use rand::RngCore;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Commitment([u8; 32]);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Opening<P> {
     pub nonce: [u8; 32],
     pub proof: P,
@@ -62,6 +62,47 @@ impl Opener {
             Err(())
         }
     }
+
+    /// Opens many openings at once, batching the underlying proof
+    /// verification into a single `Proof::verify_batch` call instead of one
+    /// independent `open` per item. For `CompactProof` that call still
+    /// evaluates one multi-exponentiation per equation; the equations of
+    /// different proofs are not merged into one. A bad proof only fails its own opening --
+    /// it neither blocks nor is masked by the others.
+    pub fn open_batch<P: Proof>(items: Vec<(&Opener, Opening<P>)>) -> Vec<Result<P, ()>> {
+        let mut transcripts: Vec<Transcript> = items
+            .iter()
+            .map(|(opener, _)| opener.transcript.clone())
+            .collect();
+
+        let proof_ok = {
+            let mut verify_items: Vec<(&P, &mut Transcript, &'static [u8])> = items
+                .iter()
+                .zip(transcripts.iter_mut())
+                .map(|((opener, opening), transcript)| (&opening.proof, transcript, opener.label))
+                .collect();
+            P::verify_batch(&mut verify_items)
+        };
+
+        items
+            .into_iter()
+            .zip(transcripts)
+            .zip(proof_ok)
+            .map(|(((opener, opening), mut transcript), proof_ok)| {
+                if !proof_ok {
+                    return Err(());
+                }
+
+                transcript.add_commited_nizk_nonce(opener.label, opening.nonce);
+                let commitment = transcript.get_commitment();
+                if commitment == opener.commitment.0 {
+                    Ok(opening.proof)
+                } else {
+                    Err(())
+                }
+            })
+            .collect()
+    }
 }
 
 impl Commitment {
@@ -149,4 +190,35 @@ mod test {
         );
         assert!(opener.open(opening).is_ok());
     }
+
+    #[test]
+    fn open_batch_localizes_a_tampered_opening() {
+        let g = GE::generator();
+        let h = GE::base_point2();
+
+        let make_commitment = |seed: &'static [u8]| {
+            let x = FE::new_random();
+            let witness = vec![Witness {
+                x,
+                kind: StatementKind::DDH { g, h },
+                label: b"x",
+            }];
+            let mut transcript_prover = Transcript::new(seed);
+            let mut transcript_verifier = Transcript::new(seed);
+            let (commitment, opening) =
+                commit_nizk::<CompactProof>(&mut transcript_prover, b"proof_name", &witness);
+            let opener = commitment.receive(&mut transcript_verifier, b"proof_name");
+            (opener, opening)
+        };
+
+        let (opener_a, opening_a) = make_commitment(b"open_batch_a");
+        let (opener_b, mut opening_b) = make_commitment(b"open_batch_b");
+
+        opening_b.proof.challenge = opening_b.proof.challenge + FE::new_random();
+
+        let results = Opener::open_batch(vec![(&opener_a, opening_a), (&opener_b, opening_b)]);
+
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
 }
@@ -0,0 +1,49 @@
+//! The cut-and-choose Fiat-Shamir scaffolding shared by [`crate::range_proof`]
+//! and [`crate::escrow`]: both commit to [`SECURITY_PARAMETER`] Paillier-
+//! encrypted masks and let a transcript-derived coin per round decide
+//! whether the verifier learns the mask alone ([`RoundOpening::Mask`]) or
+//! the mask combined with the real secret ([`RoundOpening::Combined`]).
+//! What differs between the two call sites is the equation each opening has
+//! to satisfy -- a bound check in `range_proof`, a matching group equation
+//! in `escrow` -- so only the commit/open bookkeeping lives here.
+
+use curv::BigInt;
+use merlin::Transcript;
+
+/// Number of cut-and-choose rounds. Each round a cheating prover survives
+/// only if its masks happen to land on the coin the transcript-derived
+/// challenge picks, but since the prover chooses (and can re-sample) its
+/// masks *before* that challenge is derived, too few rounds makes forging an
+/// out-of-range proof a matter of grinding fresh masks until all of them
+/// land favourably (trivial with, say, 16 rounds: ~2^16 Paillier
+/// encryptions). 128 rounds puts that grind out of reach (2^128 attempts).
+pub(crate) const SECURITY_PARAMETER: usize = 128;
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) enum RoundOpening {
+    Mask {
+        #[serde(with = "crate::serde_curv::bigint")]
+        w: BigInt,
+        #[serde(with = "crate::serde_curv::bigint")]
+        r: BigInt,
+    },
+    Combined {
+        #[serde(with = "crate::serde_curv::bigint")]
+        z: BigInt,
+        #[serde(with = "crate::serde_curv::bigint")]
+        r: BigInt,
+    },
+}
+
+/// Derives `rounds` transcript coins, one per cut-and-choose round, deciding
+/// whether that round opens as [`RoundOpening::Mask`] (`false`) or
+/// [`RoundOpening::Combined`] (`true`).
+pub(crate) fn derive_challenge_bits(
+    transcript: &mut Transcript,
+    label: &'static [u8],
+    rounds: usize,
+) -> Vec<bool> {
+    let mut bytes = vec![0u8; rounds];
+    transcript.challenge_bytes(label, &mut bytes);
+    bytes.iter().map(|byte| byte & 1 == 1).collect()
+}
@@ -0,0 +1,425 @@
+//! `t`-of-`n` distributed key generation via Pedersen/Feldman verifiable
+//! secret sharing, reusing the existing sigma-proof machinery for each
+//! participant's proof-of-possession of its own constant term.
+//!
+//! Each participant samples a degree-`(t-1)` polynomial and runs a
+//! commit-then-reveal round (via [`crate::commited_nizk::commit_nizk`])
+//! before broadcasting its Feldman commitments: a rushing adversary who
+//! waits to see everyone else's commitments before publishing its own could
+//! otherwise bias the resulting joint key, so every participant first
+//! commits to its commitments and proof-of-possession as one opaque blob and
+//! only reveals them once every commitment is in. A recipient checks a
+//! share against the sender's revealed commitments before folding it into
+//! its long-term secret share; the joint public key is the sum of every
+//! participant's constant-term commitment.
+//!
+//! Each participant's proof-of-possession is a DDH proof rather than a plain
+//! Schnorr one: it proves the same constant term `a_0` underlies both
+//! `g^{a_0}` (the participant's contribution to the joint public key) and
+//! `h^{a_0}` (its contribution to a joint *nonce-lock* point under a second,
+//! caller-supplied base `h`). Summing the latter across participants gives a
+//! combined point that the scriptless-swap `y`-lock DDH relation
+//! (`ecdsa::EncryptedSignature`) can be checked against exactly as it would
+//! against a single party's `Y`, carrying the adaptor-signature semantics
+//! over to the threshold setting.
+
+use crate::{
+    commited_nizk::{commit_nizk, Commitment, Opener, Opening},
+    nizk_sigma_proof::{CompactProof, Statement, StatementKind, Witness},
+    SSEcdsaTranscript,
+};
+use curv::{
+    elliptic::curves::traits::{ECPoint, ECScalar},
+    BigInt, FE, GE,
+};
+use merlin::Transcript;
+
+pub type ParticipantId = u32;
+
+fn scalar_from_id(id: ParticipantId) -> FE {
+    ECScalar::from(&BigInt::from(id as u64))
+}
+
+const POP_LABEL: &[u8] = b"ssecdsa_dkg_pop";
+
+struct Polynomial {
+    // a_0..a_{t-1}, so `coefficients[0]` is the secret shared by this
+    // participant and `coefficients.len()` is the threshold `t`.
+    coefficients: Vec<FE>,
+}
+
+impl Polynomial {
+    fn sample_random(t: usize, rng: &mut (impl rand::RngCore + rand::CryptoRng)) -> Self {
+        let mut x = [0u8; 32];
+        let coefficients = (0..t)
+            .map(|_| {
+                rng.fill_bytes(&mut x);
+                ECScalar::from(&BigInt::from(&x[..]))
+            })
+            .collect();
+        Polynomial { coefficients }
+    }
+
+    /// Evaluates `f(x)` via Horner's method.
+    fn evaluate(&self, x: FE) -> FE {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(FE::zero(), |acc, a| acc * x + *a)
+    }
+}
+
+/// `f(x)` evaluated "in the exponent" from Feldman commitments `C_k = g^{a_k}`,
+/// i.e. `Σ_k C_k·x^k`, computed with the same Horner recursion as
+/// [`Polynomial::evaluate`].
+fn evaluate_commitments(commitments: &[GE], x: FE) -> GE {
+    let (last, rest) = commitments.split_last().expect("at least one coefficient");
+    rest.iter()
+        .rev()
+        .fold(*last, |acc, c| (acc * x) + *c)
+}
+
+fn append_round_context(
+    transcript: &mut Transcript,
+    id: ParticipantId,
+    commitments: &[GE],
+    nonce_commitment: GE,
+) {
+    transcript.append_message(b"ssecdsa_dkg_participant", &id.to_be_bytes());
+    for c in commitments {
+        transcript.add_point(b"ssecdsa_dkg_coefficient_commitment", *c);
+    }
+    transcript.add_point(b"ssecdsa_dkg_nonce_commitment", nonce_commitment);
+}
+
+/// One participant's polynomial and the state it needs to later reveal what
+/// it committed to.
+pub struct Participant {
+    pub id: ParticipantId,
+    polynomial: Polynomial,
+    commitments: Vec<GE>,
+    nonce_commitment: GE,
+    commitment_opening: Opening<CompactProof>,
+}
+
+/// Round 1: an opaque commitment to a participant's Feldman commitments and
+/// proof-of-possession, to be broadcast before anyone reveals theirs.
+pub struct VssCommit {
+    pub participant: ParticipantId,
+    commitment: Commitment,
+}
+
+/// Round 2: a participant's actual Feldman commitments, its contribution to
+/// the joint nonce-lock point, and the opening that proves they match what
+/// it committed to in round 1.
+pub struct VssReveal {
+    pub participant: ParticipantId,
+    pub commitments: Vec<GE>,
+    pub nonce_commitment: GE,
+    opening: Opening<CompactProof>,
+}
+
+/// A participant's revealed, commitment-checked contribution -- what
+/// `verify_reveal` produces and `aggregate` consumes.
+#[derive(Clone)]
+pub struct VssBroadcast {
+    pub participant: ParticipantId,
+    pub commitments: Vec<GE>,
+    pub nonce_commitment: GE,
+    proof_of_possession: CompactProof,
+}
+
+impl Participant {
+    /// Samples this participant's polynomial and commits to its Feldman
+    /// commitments and proof-of-possession, without revealing either yet.
+    /// `h` is the shared second base the joint nonce-lock point is formed
+    /// under (`ecdsa::EncryptedSignature`'s `Y`-lock base, in the
+    /// scriptless-swap setting).
+    pub fn new_random(
+        id: ParticipantId,
+        threshold: usize,
+        h: GE,
+        transcript: &mut Transcript,
+        rng: &mut (impl rand::RngCore + rand::CryptoRng),
+    ) -> (Participant, VssCommit) {
+        let polynomial = Polynomial::sample_random(threshold, rng);
+        let g = GE::generator();
+        let a_0 = polynomial.coefficients[0];
+        let commitments: Vec<GE> = polynomial.coefficients.iter().map(|a| g * a).collect();
+        let nonce_commitment = h * a_0;
+
+        append_round_context(transcript, id, &commitments, nonce_commitment);
+
+        let (commitment, commitment_opening) = commit_nizk::<CompactProof>(
+            transcript,
+            POP_LABEL,
+            &[Witness {
+                x: a_0,
+                kind: StatementKind::DDH { g, h },
+                label: b"a_0",
+            }],
+        );
+
+        (
+            Participant {
+                id,
+                polynomial,
+                commitments,
+                nonce_commitment,
+                commitment_opening,
+            },
+            VssCommit {
+                participant: id,
+                commitment,
+            },
+        )
+    }
+
+    /// This participant's round-2 reveal, to be broadcast only once every
+    /// participant's [`VssCommit`] has been collected.
+    pub fn reveal(&self) -> VssReveal {
+        VssReveal {
+            participant: self.id,
+            commitments: self.commitments.clone(),
+            nonce_commitment: self.nonce_commitment,
+            opening: self.commitment_opening.clone(),
+        }
+    }
+
+    /// This participant's share for `recipient`, i.e. `f(recipient)`.
+    pub fn share_for(&self, recipient: ParticipantId) -> FE {
+        self.polynomial.evaluate(scalar_from_id(recipient))
+    }
+}
+
+/// Checks `reveal` against the `commitment` broadcast for the same
+/// participant in round 1, and that its proof-of-possession actually covers
+/// the revealed Feldman commitments and nonce-lock contribution (not some
+/// other pair the prover might also know a discrete log for).
+pub fn verify_reveal(
+    h: GE,
+    commit: &VssCommit,
+    reveal: VssReveal,
+    transcript: &mut Transcript,
+) -> Result<VssBroadcast, ()> {
+    if commit.participant != reveal.participant {
+        return Err(());
+    }
+
+    append_round_context(transcript, reveal.participant, &reveal.commitments, reveal.nonce_commitment);
+
+    let opener: Opener = commit.commitment.clone().receive(transcript, POP_LABEL);
+    let proof_of_possession = opener.open(reveal.opening)?;
+
+    match proof_of_possession.get_response(b"a_0").1 {
+        Statement::DDH { g: pg, gx, h: ph, hx }
+            if pg == GE::generator()
+                && ph == h
+                && gx == reveal.commitments[0]
+                && hx == reveal.nonce_commitment => {}
+        _ => return Err(()),
+    }
+
+    Ok(VssBroadcast {
+        participant: reveal.participant,
+        commitments: reveal.commitments,
+        nonce_commitment: reveal.nonce_commitment,
+        proof_of_possession,
+    })
+}
+
+/// Checks `share` (claimed to be `f(recipient)`) against `broadcast`'s
+/// Feldman commitments: `g^share == Σ_k C_k·recipient^k`.
+pub fn verify_share(broadcast: &VssBroadcast, recipient: ParticipantId, share: FE) -> bool {
+    let g = GE::generator();
+    g * share == evaluate_commitments(&broadcast.commitments, scalar_from_id(recipient))
+}
+
+/// Aggregates every participant's (already reveal-checked) broadcast and the
+/// share `recipient` received from each into `recipient`'s long-term secret
+/// share, the joint public key, and the joint nonce-lock point. Broadcasts
+/// whose share doesn't match their commitments are dropped before
+/// aggregating rather than aborting the whole run.
+pub fn aggregate(
+    broadcasts: &[VssBroadcast],
+    recipient: ParticipantId,
+    shares_received: &[(ParticipantId, FE)],
+) -> Result<(GE, GE, FE), ()> {
+    let mut joint_public_key: Option<GE> = None;
+    let mut joint_nonce_commitment: Option<GE> = None;
+    let mut secret_share = FE::zero();
+
+    for broadcast in broadcasts {
+        let share = shares_received
+            .iter()
+            .find(|(id, _)| *id == broadcast.participant)
+            .map(|(_, share)| *share);
+
+        let share = match share {
+            Some(share) if verify_share(broadcast, recipient, share) => share,
+            _ => continue,
+        };
+
+        joint_public_key = Some(match joint_public_key {
+            Some(x) => x + broadcast.commitments[0],
+            None => broadcast.commitments[0],
+        });
+        joint_nonce_commitment = Some(match joint_nonce_commitment {
+            Some(x) => x + broadcast.nonce_commitment,
+            None => broadcast.nonce_commitment,
+        });
+        secret_share = secret_share + share;
+    }
+
+    joint_public_key
+        .zip(joint_nonce_commitment)
+        .map(|(x, y)| (x, y, secret_share))
+        .ok_or(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run_dkg_round(
+        ids: &[ParticipantId],
+        threshold: usize,
+        h: GE,
+        seed: &'static [u8],
+    ) -> (Vec<Participant>, Vec<VssBroadcast>) {
+        let mut rng = rand::thread_rng();
+
+        let (participants, commits): (Vec<_>, Vec<_>) = ids
+            .iter()
+            .map(|&id| {
+                let mut transcript = Transcript::new(seed);
+                Participant::new_random(id, threshold, h, &mut transcript, &mut rng)
+            })
+            .unzip();
+
+        let broadcasts = participants
+            .iter()
+            .zip(&commits)
+            .map(|(participant, commit)| {
+                let mut transcript = Transcript::new(seed);
+                verify_reveal(h, commit, participant.reveal(), &mut transcript)
+                    .expect("every participant's reveal matches its commit")
+            })
+            .collect();
+
+        (participants, broadcasts)
+    }
+
+    /// Lagrange coefficient `L_i(0) = Π_{j≠i} (0 - x_j)/(x_i - x_j)` for
+    /// reconstructing `f(0)` from its evaluations at `ids`.
+    fn lagrange_coefficient_at_zero(ids: &[ParticipantId], i: ParticipantId) -> FE {
+        let x_i = scalar_from_id(i);
+        let one: FE = ECScalar::from(&BigInt::one());
+
+        ids.iter().filter(|&&j| j != i).fold(one, |acc, &j| {
+            let x_j = scalar_from_id(j);
+            let numerator = FE::zero().sub(&x_j.get_element());
+            let denominator = x_i.sub(&x_j.get_element());
+            acc * numerator * denominator.invert()
+        })
+    }
+
+    #[test]
+    fn two_of_three_dkg_reconstructs_a_shared_key() {
+        let threshold = 2; // degree-1 polynomials, needs 2 shares to reconstruct
+        let h = GE::base_point2();
+        let ids: Vec<ParticipantId> = vec![1, 2, 3];
+        let (participants, broadcasts) = run_dkg_round(&ids, threshold, h, b"dkg_test");
+
+        let expected_joint_public_key = broadcasts
+            .iter()
+            .map(|b| b.commitments[0])
+            .fold(None, |acc, c| Some(acc.map_or(c, |acc: GE| acc + c)))
+            .unwrap();
+        let expected_joint_nonce_commitment = broadcasts
+            .iter()
+            .map(|b| b.nonce_commitment)
+            .fold(None, |acc, c| Some(acc.map_or(c, |acc: GE| acc + c)))
+            .unwrap();
+
+        // Shares come from the participants themselves (the broadcasts
+        // alone don't carry them -- shares are sent point-to-point).
+        let mut secret_shares = Vec::with_capacity(ids.len());
+        for &recipient in &ids {
+            let shares_received: Vec<(ParticipantId, FE)> = participants
+                .iter()
+                .map(|p| (p.id, p.share_for(recipient)))
+                .collect();
+
+            let (joint_public_key, joint_nonce_commitment, secret_share) =
+                aggregate(&broadcasts, recipient, &shares_received)
+                    .expect("every broadcast and share is honest");
+
+            assert_eq!(joint_public_key, expected_joint_public_key);
+            assert_eq!(joint_nonce_commitment, expected_joint_nonce_commitment);
+            secret_shares.push((recipient, secret_share));
+        }
+
+        // Each recipient's aggregated secret_share is f(recipient) for the
+        // combined polynomial f = Σ_participant f_participant, so any
+        // `threshold`-sized subset of them should Lagrange-reconstruct
+        // f(0) -- the discrete log behind `expected_joint_public_key` --
+        // exactly, the property the DKG's output exists to provide.
+        let subset = &secret_shares[..threshold];
+        let subset_ids: Vec<ParticipantId> = subset.iter().map(|(id, _)| *id).collect();
+        let reconstructed = subset.iter().fold(FE::zero(), |acc, &(id, share)| {
+            acc + share * lagrange_coefficient_at_zero(&subset_ids, id)
+        });
+
+        assert_eq!(GE::generator() * reconstructed, expected_joint_public_key);
+    }
+
+    #[test]
+    fn a_reveal_that_does_not_match_its_commit_is_rejected() {
+        let threshold = 2;
+        let h = GE::base_point2();
+        let mut rng = rand::thread_rng();
+
+        let mut transcript = Transcript::new(b"dkg_mismatch_test");
+        let (participant, commit) = Participant::new_random(1, threshold, h, &mut transcript, &mut rng);
+
+        let mut other_transcript = Transcript::new(b"dkg_mismatch_test");
+        let (other_participant, _) = Participant::new_random(2, threshold, h, &mut other_transcript, &mut rng);
+
+        let mismatched_reveal = VssReveal {
+            participant: participant.id,
+            commitments: other_participant.commitments.clone(),
+            nonce_commitment: other_participant.nonce_commitment,
+            opening: participant.commitment_opening.clone(),
+        };
+
+        let mut transcript = Transcript::new(b"dkg_mismatch_test");
+        assert!(verify_reveal(h, &commit, mismatched_reveal, &mut transcript).is_err());
+    }
+
+    #[test]
+    fn a_tampered_share_is_dropped_instead_of_aborting() {
+        let threshold = 2;
+        let h = GE::base_point2();
+        let ids: Vec<ParticipantId> = vec![1, 2];
+        let (participants, broadcasts) = run_dkg_round(&ids, threshold, h, b"dkg_drop_test");
+
+        let recipient = 3;
+        let shares_received = vec![
+            (participants[0].id, participants[0].share_for(recipient)),
+            (
+                participants[1].id,
+                participants[1].share_for(recipient) + FE::new_random(),
+            ),
+        ];
+
+        let (joint_public_key, joint_nonce_commitment, secret_share) =
+            aggregate(&broadcasts, recipient, &shares_received)
+                .expect("participant 1's contribution alone still aggregates");
+
+        // Only participant 1's contribution made it in.
+        assert_eq!(joint_public_key, broadcasts[0].commitments[0]);
+        assert_eq!(joint_nonce_commitment, broadcasts[0].nonce_commitment);
+        assert_eq!(secret_share, participants[0].share_for(recipient));
+    }
+}
@@ -1,8 +1,10 @@
+use crate::nizk_sigma_proof::{CompactProof, Proof, Statement, StatementKind, Witness};
 use curv::{
-    arithmetic::traits::Converter,
+    arithmetic::traits::{Converter, Modulo},
     elliptic::curves::traits::{ECPoint, ECScalar},
     BigInt, FE, GE,
 };
+use merlin::Transcript;
 use secp256k1::{Message, Secp256k1};
 
 pub fn verify(message: &Message, rx: &BigInt, s: &FE, X: &GE) -> bool {
@@ -40,3 +42,248 @@ pub struct Signature {
     pub Rx: BigInt,
     pub s: FE,
 }
+
+/// An ECDSA "pre-signature" that only becomes a valid [`Signature`] once
+/// offset by the discrete log `y` of the encryption point `Y`. This is what
+/// `SignMsg1`/`SignMsg2` carry implicitly (`c_beta_redeem_missing_y_and_bob_R`,
+/// `s_beta_redeem_missing_y`) and what the Paillier-MtA partial signatures in
+/// `bob.rs` decrypt into -- the mechanism that makes atomic swaps work:
+/// whoever can later see both a `PreSignature` and the finished on-chain
+/// `Signature` can [`recover`](Self::recover) `y`.
+///
+/// This carries no proof that `R_encrypted` and `Y` share a discrete log --
+/// that's [`EncryptedSignature`], which wraps a `PreSignature` with exactly
+/// that proof for the single-signer case. The keygen round's `Y`/`R3` DDH
+/// proof already establishes it once for the whole swap, so the per-round
+/// partial signatures in `bob.rs` use `PreSignature` directly instead of
+/// re-proving it every time.
+#[derive(Debug, Clone, Copy)]
+pub struct PreSignature {
+    /// The unscaled nonce commitment `R = k·G`.
+    pub R: GE,
+    /// The nonce commitment offset by the encryption point, `R_encrypted = k·Y`.
+    /// Its x-coordinate is the `rx` that ends up in the final signature.
+    pub R_encrypted: GE,
+    pub s_hat: FE,
+}
+
+impl PreSignature {
+    /// Produce a pre-signature for `msg` under `signing_key`, encrypted to
+    /// `encryption_point`.
+    pub fn encrypt(signing_key: &FE, nonce: &FE, encryption_point: &GE, msg: &Message) -> PreSignature {
+        let g = GE::generator();
+        let R = g * nonce;
+        let R_encrypted = *encryption_point * nonce;
+        let rx: FE = ECScalar::from(&R_encrypted.x_coor().unwrap());
+        let m: FE = ECScalar::from(&BigInt::from(&msg[..]));
+        let s_hat = nonce.invert() * (m + rx * signing_key);
+
+        PreSignature { R, R_encrypted, s_hat }
+    }
+
+    /// Checks the linear equation a completed signature by `verification_key`
+    /// over `msg` would satisfy, without needing to know `y`.
+    pub fn verify_encrypted(&self, verification_key: &GE, msg: &Message) -> bool {
+        let g = GE::generator();
+        let rx: FE = ECScalar::from(&self.R_encrypted.x_coor().unwrap());
+        let m: FE = ECScalar::from(&BigInt::from(&msg[..]));
+        self.R * self.s_hat == (*verification_key * rx) + (g * m)
+    }
+
+    /// Turns `self` into a normal signature usable by [`verify`]/
+    /// [`normalize_and_verify`], given the discrete log `y` of the
+    /// encryption point it was encrypted to.
+    pub fn decrypt(&self, y: FE) -> Signature {
+        let s = self.s_hat * y.invert();
+        let Rx = self.R_encrypted.x_coor().unwrap();
+        let mut s = s.to_big_int();
+        let neg_s = FE::q() - s.clone();
+        if s > neg_s {
+            s = neg_s;
+        }
+        Signature {
+            Rx,
+            s: ECScalar::from(&s),
+        }
+    }
+
+    /// The key-leak step that makes atomic swaps work: given the finished
+    /// on-chain `signature`, extract the discrete log `y` of `encryption_point`.
+    /// Returns `None` if `signature` doesn't actually complete `self`.
+    pub fn recover(&self, encryption_point: &GE, signature: &Signature) -> Option<FE> {
+        recover_y(encryption_point, &self.s_hat, signature)
+    }
+}
+
+/// The key-leak step that makes atomic swaps work, factored out of
+/// [`PreSignature::recover`] so it can be reused once a pre-signature's
+/// `s_hat` has been further transformed (e.g. the blinding-factor division
+/// Bob's partial signatures go through in `bob.rs`) and no longer lives in a
+/// whole `PreSignature`. Given the finished on-chain `signature`, extracts
+/// the discrete log `y` of `encryption_point`, or `None` if `signature`
+/// doesn't actually complete the pre-signature `s_hat` came from.
+pub fn recover_y(encryption_point: &GE, s_hat: &FE, signature: &Signature) -> Option<FE> {
+    let q = FE::q();
+    let y_maybe = signature.s.invert() * s_hat;
+    let Y_maybe: GE = GE::generator() * y_maybe;
+
+    if Y_maybe.x_coor().unwrap() != encryption_point.x_coor().unwrap() {
+        return None;
+    }
+
+    if Y_maybe.y_coor().unwrap() != encryption_point.y_coor().unwrap() {
+        Some(ECScalar::from(&BigInt::mod_sub(&q, &y_maybe.to_big_int(), &q)))
+    } else {
+        Some(y_maybe)
+    }
+}
+
+/// A [`PreSignature`] bundled with the proof that its `R_encrypted` and the
+/// public `Y` it was encrypted to share the same discrete log `y`. Use this
+/// when the proof hasn't already been established elsewhere (e.g. a
+/// single-signer adaptor signature); when it has (e.g. the keygen-time `Y`
+/// proof covering every per-round partial signature in `bob.rs`), use
+/// [`PreSignature`] directly.
+#[derive(Debug, Clone)]
+pub struct EncryptedSignature {
+    pub pre_signature: PreSignature,
+    /// Proves `R_encrypted` and `Y` share the same discrete log `y`, i.e.
+    /// `DDH { g, gx: Y, h: R, hx: R_encrypted }`.
+    pub proof: CompactProof,
+}
+
+impl EncryptedSignature {
+    /// Produce a pre-signature for `msg` under `signing_key`, encrypted to
+    /// `encryption_key`'s public point `Y = encryption_key·G`. Only someone
+    /// who also knows `y` (here, `encryption_key`) can attach the proof that
+    /// ties the pre-signature to `Y`, which is exactly the role Alice plays
+    /// for the adaptor point `Y` in key generation.
+    pub fn encrypt(
+        transcript: &mut Transcript,
+        label: &'static [u8],
+        signing_key: &FE,
+        nonce: &FE,
+        encryption_key: &FE,
+        msg: &Message,
+    ) -> EncryptedSignature {
+        let g = GE::generator();
+        let Y = g * encryption_key;
+        let pre_signature = PreSignature::encrypt(signing_key, nonce, &Y, msg);
+
+        let proof = CompactProof::prove(
+            transcript,
+            label,
+            &[Witness {
+                x: *encryption_key,
+                kind: StatementKind::DDH { g, h: pre_signature.R },
+                label,
+            }],
+        );
+
+        EncryptedSignature {
+            pre_signature,
+            proof,
+        }
+    }
+
+    /// Checks that `self` is a valid pre-signature by `verification_key`
+    /// over `msg`, encrypted to `encryption_point`, without needing to know
+    /// `y`: the attached proof that `R_encrypted` really is `R` raised to
+    /// the same `y` behind `encryption_point`, plus the linear equation a
+    /// completed signature would satisfy.
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        label: &'static [u8],
+        verification_key: &GE,
+        encryption_point: &GE,
+        msg: &Message,
+    ) -> bool {
+        let g = GE::generator();
+        match self.proof.get_response(label) {
+            (_, Statement::DDH { g: pg, gx, h, hx })
+                if pg == g
+                    && gx == *encryption_point
+                    && h == self.pre_signature.R
+                    && hx == self.pre_signature.R_encrypted => {}
+            _ => return false,
+        }
+        if !self.proof.verify(transcript, label) {
+            return false;
+        }
+
+        self.pre_signature.verify_encrypted(verification_key, msg)
+    }
+
+    /// Turns `self` into a normal signature; see [`PreSignature::decrypt`].
+    pub fn decrypt(&self, y: FE) -> Signature {
+        self.pre_signature.decrypt(y)
+    }
+
+    /// Recovers `y`; see [`PreSignature::recover`].
+    pub fn recover(&self, encryption_point: &GE, signature: &Signature) -> Option<FE> {
+        self.pre_signature.recover(encryption_point, signature)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn msg() -> Message {
+        Message::from_slice(&[7u8; 32]).unwrap()
+    }
+
+    #[test]
+    fn pre_signature_round_trips_through_decrypt_and_recover() {
+        let x = FE::new_random();
+        let X = GE::generator() * x;
+        let k = FE::new_random();
+        let y = FE::new_random();
+        let Y = GE::generator() * y;
+        let msg = msg();
+
+        let pre_signature = PreSignature::encrypt(&x, &k, &Y, &msg);
+        assert!(pre_signature.verify_encrypted(&X, &msg));
+
+        let signature = pre_signature.decrypt(y);
+        assert!(verify(&msg, &signature.Rx, &signature.s, &X));
+
+        assert_eq!(
+            pre_signature.recover(&Y, &signature).unwrap().to_big_int(),
+            y.to_big_int()
+        );
+    }
+
+    #[test]
+    fn encrypted_signature_verifies_and_recovers_like_its_pre_signature() {
+        let x = FE::new_random();
+        let X = GE::generator() * x;
+        let k = FE::new_random();
+        let y = FE::new_random();
+        let Y = GE::generator() * y;
+        let msg = msg();
+
+        let mut prover_transcript = Transcript::new(b"encrypted_signature_test");
+        let encrypted_signature = EncryptedSignature::encrypt(
+            &mut prover_transcript,
+            b"adaptor",
+            &x,
+            &k,
+            &y,
+            &msg,
+        );
+
+        let mut verifier_transcript = Transcript::new(b"encrypted_signature_test");
+        assert!(encrypted_signature.verify(&mut verifier_transcript, b"adaptor", &X, &Y, &msg));
+
+        let signature = encrypted_signature.decrypt(y);
+        assert_eq!(
+            encrypted_signature
+                .recover(&Y, &signature)
+                .unwrap()
+                .to_big_int(),
+            y.to_big_int()
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Verifiable encryption of the swap secret `y` to a designated arbiter, so
+//! that if a party vanishes mid-protocol the arbiter can hand `y` to Bob (to
+//! complete `beta_redeem`) or to Alice (to refund) instead of the funds
+//! being stranded -- without the arbiter learning `y` during the happy
+//! path.
+//!
+//! `y` lives in `Z_q`, not in the arbiter's Paillier plaintext ring alone,
+//! so plain homomorphic Paillier encryption doesn't by itself prove
+//! anything about its relationship to the public lock `Y = g^y`. Elliptic-
+//! curve ElGamal has the opposite problem: it can only ever decrypt back to
+//! `Y`, never to the scalar `y`, since recovering a discrete log is exactly
+//! the hard problem the curve relies on. This instead reuses the
+//! cut-and-choose Fiat-Shamir technique `range_proof.rs` uses to bind a
+//! Paillier ciphertext to a value, extended so each masking round's
+//! "combined" branch checks both the Paillier equation *and* the matching
+//! group equation -- a cheating prover can't satisfy one without the other,
+//! so the ciphertext is bound to the same `y` that underlies `Y`.
+//!
+//! This is the verifiable-encryption-of-discrete-log primitive the keygen
+//! escrow extension (`alice.rs`'s `Alice1::new_with_escrow`) is built on.
+
+use curv::{
+    arithmetic::traits::{Modulo, Samplable},
+    elliptic::curves::traits::{ECPoint, ECScalar},
+    BigInt, FE, GE,
+};
+use merlin::Transcript;
+use paillier::{
+    traits::{Decrypt, EncryptWithChosenRandomness},
+    DecryptionKey, EncryptionKey, Paillier, Randomness, RawCiphertext, RawPlaintext,
+};
+
+use crate::cut_and_choose::{derive_challenge_bits, RoundOpening, SECURITY_PARAMETER};
+use crate::SSEcdsaTranscript;
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct EscrowProof {
+    #[serde(with = "crate::serde_curv::bigint_vec")]
+    mask_ciphertexts: Vec<BigInt>,
+    #[serde(with = "crate::serde_curv::point_vec")]
+    mask_points: Vec<GE>,
+    openings: Vec<RoundOpening>,
+}
+
+/// `y` Paillier-encrypted to the arbiter's `EncryptionKey`, plus the proof
+/// that the ciphertext encrypts exactly the discrete log behind `Y`.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Escrow {
+    #[serde(with = "crate::serde_curv::bigint")]
+    pub ciphertext: BigInt,
+    proof: EscrowProof,
+}
+
+/// Encrypts `y` to `arbiter_key` and proves, bound to `transcript`, that the
+/// ciphertext encrypts the discrete log behind `Y = g^y`.
+pub fn encrypt(
+    transcript: &mut Transcript,
+    label: &'static [u8],
+    arbiter_key: &EncryptionKey,
+    y: FE,
+    Y: GE,
+) -> Escrow {
+    let g = GE::generator();
+    let q = FE::q();
+    let nn = &arbiter_key.n * &arbiter_key.n;
+
+    let randomness = BigInt::sample_below(&arbiter_key.n);
+    let ciphertext = Paillier::encrypt_with_chosen_randomness(
+        arbiter_key,
+        RawPlaintext::from(y.to_big_int()),
+        &Randomness(randomness.clone()),
+    )
+    .0
+    .into_owned();
+
+    transcript.append_message(b"ss-ecdsa-poc/escrow/1.0", label);
+    transcript.add_point(b"Y", Y);
+    transcript.append_message(b"ciphertext", &BigInt::to_vec(&ciphertext));
+
+    let mut mask_ciphertexts = Vec::with_capacity(SECURITY_PARAMETER);
+    let mut mask_points = Vec::with_capacity(SECURITY_PARAMETER);
+    let mut ws = Vec::with_capacity(SECURITY_PARAMETER);
+    let mut rs = Vec::with_capacity(SECURITY_PARAMETER);
+
+    for _ in 0..SECURITY_PARAMETER {
+        let w = BigInt::sample_below(&q);
+        let r = BigInt::sample_below(&arbiter_key.n);
+        let mask_ciphertext = Paillier::encrypt_with_chosen_randomness(
+            arbiter_key,
+            RawPlaintext::from(w.clone()),
+            &Randomness(r.clone()),
+        )
+        .0
+        .into_owned();
+        let w_scalar: FE = ECScalar::from(&w);
+        let mask_point = g * w_scalar;
+
+        transcript.append_message(b"mask_ciphertext", &BigInt::to_vec(&mask_ciphertext));
+        transcript.add_point(b"mask_point", mask_point);
+
+        mask_ciphertexts.push(mask_ciphertext);
+        mask_points.push(mask_point);
+        ws.push(w);
+        rs.push(r);
+    }
+
+    let challenge_bits = derive_challenge_bits(transcript, label, SECURITY_PARAMETER);
+    let openings = challenge_bits
+        .into_iter()
+        .enumerate()
+        .map(|(i, open_combined)| {
+            if open_combined {
+                RoundOpening::Combined {
+                    z: &ws[i] + y.to_big_int(),
+                    r: BigInt::mod_mul(&rs[i], &randomness, &nn),
+                }
+            } else {
+                RoundOpening::Mask {
+                    w: ws[i].clone(),
+                    r: rs[i].clone(),
+                }
+            }
+        })
+        .collect();
+
+    Escrow {
+        ciphertext,
+        proof: EscrowProof {
+            mask_ciphertexts,
+            mask_points,
+            openings,
+        },
+    }
+}
+
+/// Verifies an [`Escrow`] produced by [`encrypt`] against the same `Y` and
+/// `transcript` context.
+pub fn verify(
+    transcript: &mut Transcript,
+    label: &'static [u8],
+    arbiter_key: &EncryptionKey,
+    Y: GE,
+    escrow: &Escrow,
+) -> bool {
+    let proof = &escrow.proof;
+    if proof.mask_ciphertexts.len() != SECURITY_PARAMETER
+        || proof.mask_points.len() != SECURITY_PARAMETER
+        || proof.openings.len() != SECURITY_PARAMETER
+    {
+        return false;
+    }
+
+    transcript.append_message(b"ss-ecdsa-poc/escrow/1.0", label);
+    transcript.add_point(b"Y", Y);
+    transcript.append_message(b"ciphertext", &BigInt::to_vec(&escrow.ciphertext));
+    for (ciphertext, point) in proof.mask_ciphertexts.iter().zip(&proof.mask_points) {
+        transcript.append_message(b"mask_ciphertext", &BigInt::to_vec(ciphertext));
+        transcript.add_point(b"mask_point", *point);
+    }
+
+    let challenge_bits = derive_challenge_bits(transcript, label, SECURITY_PARAMETER);
+    let nn = &arbiter_key.n * &arbiter_key.n;
+    let g = GE::generator();
+
+    for (((mask_ciphertext, mask_point), opening), open_combined) in proof
+        .mask_ciphertexts
+        .iter()
+        .zip(&proof.mask_points)
+        .zip(&proof.openings)
+        .zip(challenge_bits)
+    {
+        let ok = match (opening, open_combined) {
+            (RoundOpening::Mask { w, r }, false) => {
+                let w_scalar: FE = ECScalar::from(w);
+                g * w_scalar == *mask_point
+                    && Paillier::encrypt_with_chosen_randomness(
+                        arbiter_key,
+                        RawPlaintext::from(w.clone()),
+                        &Randomness(r.clone()),
+                    )
+                    .0
+                    .into_owned()
+                        == *mask_ciphertext
+            }
+            (RoundOpening::Combined { z, r }, true) => {
+                let z_scalar: FE = ECScalar::from(z);
+                g * z_scalar == *mask_point + Y
+                    && Paillier::encrypt_with_chosen_randomness(
+                        arbiter_key,
+                        RawPlaintext::from(z.clone()),
+                        &Randomness(r.clone()),
+                    )
+                    .0
+                    .into_owned()
+                        == BigInt::mod_mul(mask_ciphertext, &escrow.ciphertext, &nn)
+            }
+            _ => false,
+        };
+
+        if !ok {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// The arbiter's side: decrypts `escrow.ciphertext` back into `y`. Only
+/// meaningful once [`verify`] has passed -- an unverified ciphertext could
+/// decrypt to anything.
+pub fn decrypt(escrow: &Escrow, arbiter_key: &DecryptionKey) -> FE {
+    let plaintext = Paillier::decrypt(arbiter_key, RawCiphertext::from(escrow.ciphertext.clone()));
+    ECScalar::from(&plaintext.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paillier::traits::KeyGeneration;
+
+    #[test]
+    fn arbiter_recovers_y_from_a_verified_escrow() {
+        let (ek, dk) = Paillier::keypair().keys();
+        let y = FE::new_random();
+        let Y = GE::generator() * y;
+
+        let mut prover_transcript = Transcript::new(b"escrow_test");
+        let escrow = encrypt(&mut prover_transcript, b"escrow", &ek, y, Y);
+
+        let mut verifier_transcript = Transcript::new(b"escrow_test");
+        assert!(verify(&mut verifier_transcript, b"escrow", &ek, Y, &escrow));
+
+        assert_eq!(decrypt(&escrow, &dk).to_big_int(), y.to_big_int());
+    }
+
+    #[test]
+    fn a_ciphertext_for_a_different_y_is_rejected() {
+        let (ek, _dk) = Paillier::keypair().keys();
+        let y = FE::new_random();
+        let Y = GE::generator() * y;
+
+        let mut prover_transcript = Transcript::new(b"escrow_tamper_test");
+        let mut escrow = encrypt(&mut prover_transcript, b"escrow", &ek, y, Y);
+        escrow.ciphertext = BigInt::mod_add(&escrow.ciphertext, &BigInt::one(), &(&ek.n * &ek.n));
+
+        let mut verifier_transcript = Transcript::new(b"escrow_tamper_test");
+        assert!(!verify(
+            &mut verifier_transcript,
+            b"escrow",
+            &ek,
+            Y,
+            &escrow
+        ));
+    }
+}
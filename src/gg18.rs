@@ -0,0 +1,524 @@
+//! GG18-style multiplicative-to-additive (MtA) two-party ECDSA signing, a
+//! second signing backend alongside the Lindell-2017 Paillier-decryption one
+//! `alice.rs`/`bob.rs` are hard-wired to. Each party holds an additive share
+//! of the signing key (`x = x_a + x_b`) and, per signature, runs two MtA
+//! instances -- one for `k·γ` and one for `k·x` -- so the nonce inverse is
+//! formed additively without either party ever holding `k` whole. Unlike
+//! Lindell-2017 this needs no interactive PDL round-trip
+//! (`PdlMsg1`-`PdlMsg4`): each party's encrypted nonce share instead travels
+//! with a non-interactive [`crate::range_proof`] in the same message.
+//!
+//! The `y`-lock on the nonce is kept exactly as the Lindell-2017 backend
+//! applies it to `s_beta_redeem_missing_y`, so adaptor/scriptless behaviour
+//! is unchanged between backends.
+
+use crate::{ecdsa, messages::Signature};
+use curv::{
+    arithmetic::traits::{Converter, Modulo, Samplable},
+    elliptic::curves::traits::{ECPoint, ECScalar},
+    BigInt, FE, GE,
+};
+use merlin::Transcript;
+use paillier::{
+    traits::{Add, Decrypt, Encrypt, EncryptWithChosenRandomness, KeyGeneration, Mul},
+    DecryptionKey, EncryptionKey, Paillier, Randomness, RawCiphertext, RawPlaintext,
+};
+use secp256k1::Message;
+
+/// Encrypts `x` under `ek` with freshly sampled randomness, returning both --
+/// unlike the bare [`Paillier::encrypt`] calls this replaces, the randomness
+/// has to be kept around so the ciphertext's accompanying
+/// [`crate::range_proof`] can be built against it.
+fn encrypt_random(ek: &EncryptionKey, x: &BigInt) -> (BigInt, BigInt) {
+    let r = BigInt::sample_below(&ek.n);
+    let c = Paillier::encrypt_with_chosen_randomness(
+        ek,
+        RawPlaintext::from(x.clone()),
+        &Randomness(r.clone()),
+    );
+    (c.0.into_owned(), r)
+}
+
+/// Selects which two-party ECDSA backend a signing round runs: the original
+/// Paillier-decryption flow, or this module's MtA-based one. The
+/// `ss_ecdsa_poc` binary switches on this instead of hard-coding `lindell_2017`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SigningBackend {
+    Lindell2017,
+    Gg18Mta,
+}
+
+/// What a party sends back in response to an MtA request: `Enc_pk(a·b - β)`
+/// for a mask `β` only the responder knows, alongside keeping `β` as its own
+/// additive share.
+pub struct MtaResponse {
+    ciphertext: BigInt,
+}
+
+/// Runs the responder's half of an MtA on `c_a = Enc_pk(a)` (received from
+/// the requester) against this party's scalar `b`: returns the ciphertext to
+/// send back plus this party's additive share `β` of `a·b`.
+pub fn mta_respond(ek: &EncryptionKey, c_a: &BigInt, b: FE) -> (MtaResponse, FE) {
+    let q = FE::q();
+    let beta_tag = FE::new_random();
+
+    let c_ab = Paillier::mul(
+        ek,
+        RawCiphertext::from(c_a.clone()),
+        RawPlaintext::from(b.to_big_int()),
+    );
+    let enc_neg_beta_tag = Paillier::encrypt(
+        ek,
+        RawPlaintext::from(BigInt::mod_sub(&q, &beta_tag.to_big_int(), &q)),
+    );
+    let c_b = Paillier::add(ek, c_ab, enc_neg_beta_tag);
+
+    (
+        MtaResponse {
+            ciphertext: c_b.0.into_owned(),
+        },
+        beta_tag,
+    )
+}
+
+/// The requester's half: decrypts the responder's reply into its own
+/// additive share `α` of `a·b`, so that `α + β == a·b`.
+pub fn mta_finish(dk: &DecryptionKey, response: &MtaResponse) -> FE {
+    let plaintext = Paillier::decrypt(dk, RawCiphertext::from(response.ciphertext.clone()));
+    ECScalar::from(&plaintext.0)
+}
+
+/// One party's additive key share plus the Paillier keypair it uses to run
+/// MtA as the requester for its own secrets.
+pub struct Gg18KeyShare {
+    pub x_i: FE,
+    pub X: GE,
+    pub ek: EncryptionKey,
+    pub dk: DecryptionKey,
+}
+
+/// This party's contribution to one signing round: its nonce share `k_i`
+/// and the multiplicative mask `gamma_i` GG18 uses to blind `k` while
+/// inverting it additively.
+pub struct Gg18NonceShare {
+    pub k_i: FE,
+    pub gamma_i: FE,
+}
+
+impl Gg18NonceShare {
+    pub fn new_random() -> Self {
+        Gg18NonceShare {
+            k_i: FE::new_random(),
+            gamma_i: FE::new_random(),
+        }
+    }
+}
+
+/// Combines this party's additive share of `k·x` (`sigma_i`) with its raw
+/// nonce share `k_i` into its additive share `s_i` of the final signature,
+/// folding in the adaptor secret `y` exactly the way the Lindell-2017
+/// backend folds it into `s_beta_redeem_missing_y`. `rx` must already be the
+/// `x`-coordinate of `R = Γ^{δ⁻¹}` -- the `δ⁻¹` inversion happens there, when
+/// `R` is formed, not here: `s = k·(m + rx·x)` verifies against `R = g^{k⁻¹}`
+/// exactly as the usual `s = k⁻¹·(m + rx·x)` verifies against `R = g^{k}`.
+pub fn partial_signature(k_i: FE, sigma_i: FE, rx: FE, m: FE, y_lock: FE) -> FE {
+    ((m * k_i) + (rx * sigma_i)) * y_lock
+}
+
+/// `δ = Σ_i (k_i·γ_i + α_{i} + β_{i})`, combined in the clear once both
+/// parties have exchanged their `delta_i` shares -- this is the only value
+/// GG18 reveals before the signature itself, and it carries no information
+/// about `k` on its own since `γ` is an independent per-signature mask.
+pub fn combine_delta(delta_shares: &[FE]) -> FE {
+    delta_shares
+        .iter()
+        .fold(FE::zero(), |acc, share| acc + share)
+}
+
+/// The counterparty's public key-share material, learned during GG18's own
+/// keygen (out of scope here, same as the Lindell-2017 backend's keygen
+/// rounds being out of scope for `extract_partial_sig`): the share of the
+/// combined public key `X`, and the Paillier key this party must encrypt its
+/// nonce share under to let the counterparty run the responder side of MtA.
+pub struct Gg18CounterpartyKeyShare {
+    pub X: GE,
+    pub ek: EncryptionKey,
+}
+
+pub struct Gg18SignMsg1 {
+    pub Gamma: GE,
+    pub c_k: BigInt,
+    /// Proves `c_k` encrypts `k_i` in range, exactly the MtA range proof
+    /// `chunk1-2`'s review asked for -- without it a malicious party could
+    /// feed an out-of-range `k_i` into both `mta_respond` calls this
+    /// ciphertext is reused across.
+    pub k_range_proof: crate::range_proof::RangeProof,
+}
+
+pub struct Gg18SignMsg2 {
+    pub Gamma: GE,
+    pub c_k: BigInt,
+    pub k_range_proof: crate::range_proof::RangeProof,
+    pub mta_gamma: MtaResponse,
+    pub mta_x: MtaResponse,
+}
+
+pub struct Gg18SignMsg3 {
+    pub mta_gamma: MtaResponse,
+    pub mta_x: MtaResponse,
+    pub delta_i: FE,
+}
+
+pub struct Gg18SignMsg4 {
+    pub delta_i: FE,
+    pub s_i: FE,
+}
+
+/// Bob's side of an MtA-based signing round -- the initiator, mirroring
+/// `Bob1::new` being the one who speaks first in the Lindell-2017 flow.
+/// Constructed straight from an existing [`Gg18KeyShare`] since this module
+/// only covers signing, not GG18's own (different) keygen.
+pub struct Gg18Bob1 {
+    key_share: Gg18KeyShare,
+    counterparty: Gg18CounterpartyKeyShare,
+    nonce_share: Gg18NonceShare,
+    msg: Message,
+    y_lock: FE,
+    transcript: Transcript,
+}
+
+impl Gg18Bob1 {
+    /// `y_lock` is folded multiplicatively into this party's own `s_i` share,
+    /// exactly as the Lindell-2017 backend folds `y` into
+    /// `s_beta_redeem_missing_y` -- pass `FE::new_random()`'s inverse (or
+    /// whatever the swap ceremony's `y` is) to reproduce adaptor behaviour,
+    /// or the multiplicative identity for a plain signature. `transcript`
+    /// should be a fresh, identically-seeded transcript to the one passed to
+    /// the matching [`Gg18Alice1::new`] -- both sides keep their own clone in
+    /// lockstep by appending identical public data in identical order, the
+    /// same convention `alice.rs`/`bob.rs` use for their keygen round.
+    pub fn new(
+        key_share: Gg18KeyShare,
+        counterparty: Gg18CounterpartyKeyShare,
+        msg: Message,
+        y_lock: FE,
+        transcript: &mut Transcript,
+    ) -> (Self, Gg18SignMsg1) {
+        let nonce_share = Gg18NonceShare::new_random();
+        let Gamma = GE::generator() * nonce_share.gamma_i;
+        let (c_k, r_k) = encrypt_random(&key_share.ek, &nonce_share.k_i.to_big_int());
+        let k_range_proof = crate::range_proof::prove(
+            transcript,
+            b"gg18_mta_k",
+            &key_share.ek,
+            &c_k,
+            &r_k,
+            &nonce_share.k_i.to_big_int(),
+            &FE::q(),
+        );
+
+        (
+            Gg18Bob1 {
+                key_share,
+                counterparty,
+                nonce_share,
+                msg,
+                y_lock,
+                transcript: transcript.clone(),
+            },
+            Gg18SignMsg1 {
+                Gamma,
+                c_k,
+                k_range_proof,
+            },
+        )
+    }
+
+    /// Responds to Alice's nonce commitment as the MtA responder for both
+    /// the `k·γ` and `k·x` cross terms, decrypts her response to this
+    /// party's own earlier commitment, and folds both into this party's
+    /// `delta_i`/`sigma_i` shares. Rejects the message if Alice's `c_k`
+    /// doesn't come with a valid range proof -- otherwise she could feed an
+    /// out-of-range `k_i` into both `mta_respond` calls below.
+    pub fn receive_message(self, msg: Gg18SignMsg2) -> Result<(Gg18Bob2, Gg18SignMsg3), ()> {
+        let mut transcript = self.transcript;
+        if !crate::range_proof::verify(
+            &mut transcript,
+            b"gg18_mta_k",
+            &self.counterparty.ek,
+            &msg.c_k,
+            &FE::q(),
+            &msg.k_range_proof,
+        ) {
+            return Err(());
+        }
+
+        let alpha_gamma = mta_finish(&self.key_share.dk, &msg.mta_gamma);
+        let alpha_x = mta_finish(&self.key_share.dk, &msg.mta_x);
+
+        let (mta_gamma, beta_gamma) =
+            mta_respond(&self.counterparty.ek, &msg.c_k, self.nonce_share.gamma_i);
+        let (mta_x, beta_x) = mta_respond(&self.counterparty.ek, &msg.c_k, self.key_share.x_i);
+
+        let delta_i = self.nonce_share.k_i * self.nonce_share.gamma_i + alpha_gamma + beta_gamma;
+        let sigma_i = self.nonce_share.k_i * self.key_share.x_i + alpha_x + beta_x;
+        let Gamma_total = GE::generator() * self.nonce_share.gamma_i + msg.Gamma;
+
+        Ok((
+            Gg18Bob2 {
+                X: self.key_share.X + self.counterparty.X,
+                k_i: self.nonce_share.k_i,
+                sigma_i,
+                delta_i,
+                Gamma_total,
+                msg: self.msg,
+                y_lock: self.y_lock,
+            },
+            Gg18SignMsg3 {
+                mta_gamma,
+                mta_x,
+                delta_i,
+            },
+        ))
+    }
+}
+
+pub struct Gg18Bob2 {
+    X: GE,
+    k_i: FE,
+    sigma_i: FE,
+    delta_i: FE,
+    Gamma_total: GE,
+    msg: Message,
+    y_lock: FE,
+}
+
+impl Gg18Bob2 {
+    /// Completes the round: combines both parties' `delta_i` shares into
+    /// `δ = k·γ`, forms the nonce point `R = Γ^{δ⁻¹} = g^{k⁻¹}`, and adds
+    /// Alice's signature share to this party's own to get the final
+    /// signature.
+    pub fn receive_message(self, msg: Gg18SignMsg4) -> Result<Signature, ()> {
+        let delta_inv = combine_delta(&[self.delta_i, msg.delta_i]).invert();
+        let R = self.Gamma_total * delta_inv;
+        let rx = R.x_coor().ok_or(())?;
+        let rx: FE = ECScalar::from(&rx);
+        let m: FE = ECScalar::from(&BigInt::from(&self.msg[..]));
+
+        let s_i = partial_signature(self.k_i, self.sigma_i, rx, m, self.y_lock);
+        let s = s_i + msg.s_i;
+
+        ecdsa::normalize_and_verify(&self.msg, &self.X, &s, &R)
+    }
+}
+
+/// Alice's side of an MtA-based signing round -- the responder, mirroring
+/// `Alice1::new` waiting on Bob's first message in the Lindell-2017 flow.
+/// Unlike that flow, Alice isn't the one who ends up with the finished
+/// [`Signature`] here: [`Gg18Alice2::receive_message`] hands Bob everything
+/// he needs (her `delta_i` and `s_i` shares) to combine and verify it
+/// himself in [`Gg18Bob2::receive_message`].
+pub struct Gg18Alice1 {
+    key_share: Gg18KeyShare,
+    counterparty: Gg18CounterpartyKeyShare,
+    nonce_share: Gg18NonceShare,
+    msg: Message,
+    y_lock: FE,
+    transcript: Transcript,
+}
+
+impl Gg18Alice1 {
+    /// `transcript` should be a fresh, identically-seeded transcript to the
+    /// one passed to the matching [`Gg18Bob1::new`] -- see that function's
+    /// doc comment for why a clone each side, rather than a shared
+    /// reference, is enough to keep them in lockstep.
+    pub fn new(
+        key_share: Gg18KeyShare,
+        counterparty: Gg18CounterpartyKeyShare,
+        msg: Message,
+        y_lock: FE,
+        transcript: &mut Transcript,
+    ) -> Self {
+        let nonce_share = Gg18NonceShare::new_random();
+
+        Gg18Alice1 {
+            key_share,
+            counterparty,
+            nonce_share,
+            msg,
+            y_lock,
+            transcript: transcript.clone(),
+        }
+    }
+
+    /// Responds to Bob's nonce commitment as the MtA responder for both
+    /// cross terms, and sends this party's own `Γ` commitment back so Bob
+    /// can run the matching pair in the other direction. Rejects the message
+    /// if Bob's `c_k` doesn't come with a valid range proof, for the same
+    /// reason [`Gg18Bob1::receive_message`] checks Alice's.
+    pub fn receive_message(self, msg: Gg18SignMsg1) -> Result<(Gg18Alice2, Gg18SignMsg2), ()> {
+        let mut transcript = self.transcript;
+        if !crate::range_proof::verify(
+            &mut transcript,
+            b"gg18_mta_k",
+            &self.counterparty.ek,
+            &msg.c_k,
+            &FE::q(),
+            &msg.k_range_proof,
+        ) {
+            return Err(());
+        }
+
+        let (mta_gamma, beta_gamma) =
+            mta_respond(&self.counterparty.ek, &msg.c_k, self.nonce_share.gamma_i);
+        let (mta_x, beta_x) = mta_respond(&self.counterparty.ek, &msg.c_k, self.key_share.x_i);
+
+        let Gamma = GE::generator() * self.nonce_share.gamma_i;
+        let (c_k, r_k) = encrypt_random(&self.key_share.ek, &self.nonce_share.k_i.to_big_int());
+        let k_range_proof = crate::range_proof::prove(
+            &mut transcript,
+            b"gg18_mta_k",
+            &self.key_share.ek,
+            &c_k,
+            &r_k,
+            &self.nonce_share.k_i.to_big_int(),
+            &FE::q(),
+        );
+
+        Ok((
+            Gg18Alice2 {
+                key_share: self.key_share,
+                nonce_share: self.nonce_share,
+                msg: self.msg,
+                y_lock: self.y_lock,
+                their_Gamma: msg.Gamma,
+                beta_gamma,
+                beta_x,
+            },
+            Gg18SignMsg2 {
+                Gamma,
+                c_k,
+                k_range_proof,
+                mta_gamma,
+                mta_x,
+            },
+        ))
+    }
+}
+
+pub struct Gg18Alice2 {
+    key_share: Gg18KeyShare,
+    nonce_share: Gg18NonceShare,
+    msg: Message,
+    y_lock: FE,
+    their_Gamma: GE,
+    beta_gamma: FE,
+    beta_x: FE,
+}
+
+impl Gg18Alice2 {
+    /// Finishes this party's own MtA requests (Bob's response to the nonce
+    /// commitment sent above), folds them together with the responder
+    /// shares computed a round ago into `delta_i`/`sigma_i`, forms the nonce
+    /// point `R = Γ^{δ⁻¹} = g^{k⁻¹}`, and produces this party's signature
+    /// share. Bob recombines it with his own and verifies the result in
+    /// [`Gg18Bob2::receive_message`]; Alice never sees the finished signature
+    /// here.
+    pub fn receive_message(self, msg: Gg18SignMsg3) -> Gg18SignMsg4 {
+        let alpha_gamma = mta_finish(&self.key_share.dk, &msg.mta_gamma);
+        let alpha_x = mta_finish(&self.key_share.dk, &msg.mta_x);
+
+        let delta_i =
+            self.nonce_share.k_i * self.nonce_share.gamma_i + alpha_gamma + self.beta_gamma;
+        let sigma_i = self.nonce_share.k_i * self.key_share.x_i + alpha_x + self.beta_x;
+
+        let delta_inv = combine_delta(&[delta_i, msg.delta_i]).invert();
+        let Gamma_total = self.their_Gamma + GE::generator() * self.nonce_share.gamma_i;
+        let R = Gamma_total * delta_inv;
+        let rx: FE = ECScalar::from(&R.x_coor().expect("negligible probability of infinity"));
+        let m: FE = ECScalar::from(&BigInt::from(&self.msg[..]));
+
+        let s_i = partial_signature(self.nonce_share.k_i, sigma_i, rx, m, self.y_lock);
+
+        Gg18SignMsg4 { delta_i, s_i }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mta_shares_add_up_to_the_product() {
+        let a = FE::new_random();
+        let b = FE::new_random();
+
+        let (ek, dk) = Paillier::keypair().keys();
+        let c_a = Paillier::encrypt(&ek, RawPlaintext::from(a.to_big_int()));
+
+        let (response, beta) = mta_respond(&ek, &c_a.0, b);
+        let alpha = mta_finish(&dk, &response);
+
+        assert_eq!((alpha + beta).to_big_int(), (a * b).to_big_int());
+    }
+
+    #[test]
+    fn gg18_round_produces_a_signature_that_verifies() {
+        let x_bob = FE::new_random();
+        let x_alice = FE::new_random();
+        let X_bob = GE::generator() * x_bob;
+        let X_alice = GE::generator() * x_alice;
+        let X = X_bob + X_alice;
+
+        let (ek_bob, dk_bob) = Paillier::keypair().keys();
+        let (ek_alice, dk_alice) = Paillier::keypair().keys();
+
+        let bob_key_share = Gg18KeyShare {
+            x_i: x_bob,
+            X: X_bob,
+            ek: ek_bob.clone(),
+            dk: dk_bob,
+        };
+        let alice_counterparty = Gg18CounterpartyKeyShare { X: X_bob, ek: ek_bob };
+        let alice_key_share = Gg18KeyShare {
+            x_i: x_alice,
+            X: X_alice,
+            ek: ek_alice.clone(),
+            dk: dk_alice,
+        };
+        let bob_counterparty = Gg18CounterpartyKeyShare { X: X_alice, ek: ek_alice };
+
+        let msg = Message::from_slice(&[7u8; 32]).unwrap();
+        let y_lock: FE = ECScalar::from(&BigInt::from(1));
+
+        let mut bob_transcript = Transcript::new(b"gg18_sign");
+        let mut alice_transcript = Transcript::new(b"gg18_sign");
+
+        let (bob, sign_msg_1) = Gg18Bob1::new(
+            bob_key_share,
+            bob_counterparty,
+            msg,
+            y_lock,
+            &mut bob_transcript,
+        );
+        let alice = Gg18Alice1::new(
+            alice_key_share,
+            alice_counterparty,
+            msg,
+            y_lock,
+            &mut alice_transcript,
+        );
+        let (alice, sign_msg_2) = alice
+            .receive_message(sign_msg_1)
+            .expect("Bob's k range proof should verify");
+        let (bob, sign_msg_3) = bob
+            .receive_message(sign_msg_2)
+            .expect("Alice's k range proof should verify");
+        let sign_msg_4 = alice.receive_message(sign_msg_3);
+        let signature = bob
+            .receive_message(sign_msg_4)
+            .expect("GG18 round should produce a valid signature");
+
+        assert!(ecdsa::verify(&msg, &signature.Rx, &signature.s, &X));
+    }
+}
@@ -2,9 +2,18 @@
 pub mod alice;
 pub mod bob;
 pub mod commited_nizk;
+mod cut_and_choose;
+pub mod dkg;
 pub mod ecdsa;
+pub mod escrow;
+pub mod gg18;
 pub mod messages;
 pub mod nizk_sigma;
+pub mod onchain;
+pub mod oracle;
+pub mod range_proof;
+pub mod round_based;
+mod serde_curv;
 
 use curv::{
     elliptic::curves::traits::{ECPoint, ECScalar},
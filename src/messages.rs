@@ -1,48 +1,84 @@
 use crate::{
     commited_nizk::{Commitment, Opening},
     nizk_sigma_proof::{CompactProof, LabelledStatement, Statement},
+    serde_curv,
 };
-use bitcoin_hashes::Hash;
 use curv::{elliptic::curves::traits::ECPoint, BigInt, FE, GE};
 use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::{party_one, party_two};
+use serde::{Deserialize, Serialize};
+use zk_paillier::zkproofs::NICorrectKeyProof;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AlicePoints {
+    #[serde(with = "serde_curv::point")]
     pub X_beta: GE,
+    #[serde(with = "serde_curv::point")]
     pub R_beta_redeem: GE,
+    #[serde(with = "serde_curv::point")]
     pub R_beta_refund: GE,
+    #[serde(with = "serde_curv::point")]
+    pub R_beta_cancel: GE,
+    #[serde(with = "serde_curv::point")]
+    pub R_beta_punish: GE,
+    #[serde(with = "serde_curv::point")]
     pub R3: GE,
+    #[serde(with = "serde_curv::point")]
     pub Y: GE,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AliceResponses {
+    #[serde(with = "serde_curv::scalar")]
     pub X_beta: FE,
+    #[serde(with = "serde_curv::scalar")]
     pub R_beta_redeem: FE,
+    #[serde(with = "serde_curv::scalar")]
     pub R_beta_refund: FE,
+    #[serde(with = "serde_curv::scalar")]
+    pub R_beta_cancel: FE,
+    #[serde(with = "serde_curv::scalar")]
+    pub R_beta_punish: FE,
+    #[serde(with = "serde_curv::scalar")]
     pub Y_R3: FE,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BobPoints {
+    #[serde(with = "serde_curv::point")]
     pub X_alpha: GE,
+    #[serde(with = "serde_curv::point")]
     pub X_beta: GE,
+    #[serde(with = "serde_curv::point")]
     pub R_beta_redeem: GE,
+    #[serde(with = "serde_curv::point")]
     pub R_beta_refund: GE,
+    #[serde(with = "serde_curv::point")]
+    pub R_beta_cancel: GE,
+    #[serde(with = "serde_curv::point")]
+    pub R_beta_punish: GE,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BobResponses {
+    #[serde(with = "serde_curv::scalar")]
     pub X_alpha: FE,
+    #[serde(with = "serde_curv::scalar")]
     pub X_beta: FE,
+    #[serde(with = "serde_curv::scalar")]
     pub R_beta_redeem: FE,
+    #[serde(with = "serde_curv::scalar")]
     pub R_beta_refund: FE,
+    #[serde(with = "serde_curv::scalar")]
+    pub R_beta_cancel: FE,
+    #[serde(with = "serde_curv::scalar")]
+    pub R_beta_punish: FE,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommitmentOpening {
     pub nonce: [u8; 32],
     pub points: BobPoints,
+    #[serde(with = "serde_curv::scalar")]
     pub challenge: FE,
     pub responses: BobResponses,
 }
@@ -67,6 +103,10 @@ impl From<CompactProof> for KeyGenMsg2 {
             extract_schnorr!(proof, b"R_beta_redeem_alice");
         let (R_beta_refund, R_beta_refund_response) =
             extract_schnorr!(proof, b"R_beta_refund_alice");
+        let (R_beta_cancel, R_beta_cancel_response) =
+            extract_schnorr!(proof, b"R_beta_cancel_alice");
+        let (R_beta_punish, R_beta_punish_response) =
+            extract_schnorr!(proof, b"R_beta_punish_alice");
         KeyGenMsg2 {
             challenge: proof.challenge,
             points: AlicePoints {
@@ -74,14 +114,19 @@ impl From<CompactProof> for KeyGenMsg2 {
                 X_beta,
                 R_beta_redeem,
                 R_beta_refund,
+                R_beta_cancel,
+                R_beta_punish,
                 R3,
             },
             responses: AliceResponses {
                 X_beta: X_beta_response,
                 R_beta_redeem: R_beta_redeem_response,
                 R_beta_refund: R_beta_refund_response,
+                R_beta_cancel: R_beta_cancel_response,
+                R_beta_punish: R_beta_punish_response,
                 Y_R3: Y_R3_response,
             },
+            escrow: None,
         }
     }
 }
@@ -124,6 +169,26 @@ impl From<KeyGenMsg2> for CompactProof {
                         },
                     },
                 ),
+                (
+                    responses.R_beta_cancel,
+                    LabelledStatement {
+                        label: b"R_beta_cancel_alice",
+                        statement: Statement::Schnorr {
+                            g,
+                            gx: points.R_beta_cancel,
+                        },
+                    },
+                ),
+                (
+                    responses.R_beta_punish,
+                    LabelledStatement {
+                        label: b"R_beta_punish_alice",
+                        statement: Statement::Schnorr {
+                            g,
+                            gx: points.R_beta_punish,
+                        },
+                    },
+                ),
                 (
                     responses.Y_R3,
                     LabelledStatement {
@@ -150,6 +215,8 @@ impl From<Opening<CompactProof>> for CommitmentOpening {
         let (X_alpha, X_alpha_response) = extract_schnorr!(proof, b"X_alpha_bob");
         let (R_beta_redeem, R_beta_redeem_response) = extract_schnorr!(proof, b"R_beta_redeem_bob");
         let (R_beta_refund, R_beta_refund_response) = extract_schnorr!(proof, b"R_beta_refund_bob");
+        let (R_beta_cancel, R_beta_cancel_response) = extract_schnorr!(proof, b"R_beta_cancel_bob");
+        let (R_beta_punish, R_beta_punish_response) = extract_schnorr!(proof, b"R_beta_punish_bob");
 
         CommitmentOpening {
             challenge: proof.challenge,
@@ -159,12 +226,16 @@ impl From<Opening<CompactProof>> for CommitmentOpening {
                 X_alpha,
                 R_beta_redeem,
                 R_beta_refund,
+                R_beta_cancel,
+                R_beta_punish,
             },
             responses: BobResponses {
                 X_beta: X_beta_response,
                 X_alpha: X_alpha_response,
                 R_beta_redeem: R_beta_redeem_response,
                 R_beta_refund: R_beta_refund_response,
+                R_beta_cancel: R_beta_cancel_response,
+                R_beta_punish: R_beta_punish_response,
             },
         }
     }
@@ -220,6 +291,26 @@ impl From<CommitmentOpening> for Opening<CompactProof> {
                             },
                         },
                     ),
+                    (
+                        responses.R_beta_cancel,
+                        LabelledStatement {
+                            label: b"R_beta_cancel_bob",
+                            statement: Statement::Schnorr {
+                                g,
+                                gx: points.R_beta_cancel,
+                            },
+                        },
+                    ),
+                    (
+                        responses.R_beta_punish,
+                        LabelledStatement {
+                            label: b"R_beta_punish_bob",
+                            statement: Statement::Schnorr {
+                                g,
+                                gx: points.R_beta_punish,
+                            },
+                        },
+                    ),
                 ],
             },
         }
@@ -227,22 +318,34 @@ impl From<CommitmentOpening> for Opening<CompactProof> {
 }
 
 // Bob => Alice
+#[derive(Serialize, Deserialize)]
 pub struct KeyGenMsg1 {
     pub commitment: Commitment,
 }
 
 // Alice => Bob
+#[derive(Serialize, Deserialize)]
 pub struct KeyGenMsg2 {
+    #[serde(with = "serde_curv::scalar")]
     pub challenge: FE,
     pub points: AlicePoints,
     pub responses: AliceResponses,
+    /// `y` verifiably encrypted to a designated arbiter, present only when
+    /// keygen was run in escrow mode (see `Alice1::new_with_escrow`).
+    pub escrow: Option<crate::escrow::Escrow>,
 }
 
 // Bob => Alice
+//
+// `N_and_c` and `paillier_correct_key_proof` derive their own
+// (de)serialization from their owning crates (`multi_party_ecdsa`,
+// `zk_paillier`), and `paillier_range_proof` from `range_proof`, rather than
+// here, the same way `KeyGenMsg1`'s `Commitment` does from `commited_nizk`.
+#[derive(Serialize, Deserialize)]
 pub struct KeyGenMsg3 {
     pub commitment_opening: CommitmentOpening,
     pub N_and_c: party_two::PaillierPublic,
-    pub paillier_range_proof: RangeProofNi,
+    pub paillier_range_proof: crate::range_proof::RangeProof,
     pub paillier_correct_key_proof: NICorrectKeyProof,
 }
 
@@ -256,41 +359,36 @@ pub type PdlMsg3 = party_two::PDLSecondMessage;
 pub type PdlMsg4 = party_one::PDLSecondMessage;
 
 // Alice => Bob
-pub struct SignMsg3 {
+#[derive(Serialize, Deserialize)]
+pub struct SignMsg1 {
+    #[serde(with = "serde_curv::bigint")]
     pub c_beta_redeem_missing_y_and_bob_R: BigInt,
+    #[serde(with = "serde_curv::bigint")]
     pub c_beta_refund_missing_bob_R: BigInt,
+    #[serde(with = "serde_curv::bigint")]
+    pub c_beta_cancel_missing_bob_R: BigInt,
+    #[serde(with = "serde_curv::bigint")]
+    pub c_beta_punish_missing_bob_R: BigInt,
 }
 
 // Bob => Alice
-pub struct SignMsg4 {
+#[derive(Serialize, Deserialize)]
+pub struct SignMsg2 {
+    #[serde(with = "serde_curv::scalar")]
     pub s_beta_redeem_missing_y: FE,
-    pub s_beta_refund: FE,
 }
 
 // Alice => Blockchain
+#[derive(Serialize, Deserialize)]
 pub struct BlockchainMsg {
-    pub signature: Signature,
+    pub sig_beta_redeem: Signature,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Signature {
+    #[serde(with = "serde_curv::bigint")]
     pub Rx: BigInt,
+    #[serde(with = "serde_curv::scalar")]
     pub s: FE,
 }
 
-use zk_paillier::zkproofs::{NICorrectKeyProof, RangeProofNi};
-// These are actually be determined from the assets and joint public keys etc
-#[inline]
-pub fn beta_redeem_tx() -> secp256k1::Message {
-    secp256k1::Message::from_slice(
-        &bitcoin_hashes::sha256d::Hash::hash(b"Pay from joint output X to Alice 10 BTC")[..],
-    )
-    .unwrap()
-}
-
-#[inline]
-pub fn beta_refund_tx() -> secp256k1::Message {
-    secp256k1::Message::from_slice(
-        &bitcoin_hashes::sha256d::Hash::hash(b"Pay from joint output X to Bob 10 BTC")[..],
-    )
-    .unwrap()
-}
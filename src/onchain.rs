@@ -0,0 +1,218 @@
+//! A real Bitcoin transaction layer for the beta leg of the swap, mirroring
+//! the lock/cancel/redeem/refund/punish split used in production scriptless
+//! swaps. Builds actual segwit v0 transactions against the shared lock
+//! output and computes the BIP143 sighashes the 2-party ECDSA signing rounds
+//! in `alice.rs`/`bob.rs` sign over, instead of handing each other opaque
+//! message hashes.
+//!
+//! On top of the plain refund, a `TxCancel` -> `TxPunish` path lets the
+//! honest party punish a counterparty who locks funds but then stalls,
+//! after a configurable relative timelock.
+
+use bitcoin::blockdata::{opcodes::all as opcodes, script::Builder};
+use bitcoin::util::bip143::SighashComponents;
+use bitcoin::{Address, OutPoint, PublicKey, Script, Transaction, TxIn, TxOut, Txid};
+use curv::{elliptic::curves::traits::ECPoint, GE};
+
+/// Converts one of this crate's `curv` points into the `rust-bitcoin`
+/// public key type transaction/script building expects.
+pub fn to_bitcoin_pubkey(point: &GE) -> PublicKey {
+    PublicKey::from_slice(&point.get_element().serialize()[..]).expect("valid compressed point")
+}
+
+/// The shared lock output the swap's combined two-party-ECDSA public key
+/// (`X_beta` in `alice.rs`/`bob.rs`) spends from. A single-key output, not a
+/// multisig: the entire point of the 2-party ECDSA rounds is that they
+/// produce one ordinary-looking signature under one combined public key
+/// rather than two independent ones.
+#[derive(Clone, Debug)]
+pub struct LockDescriptor {
+    pub combined_key: PublicKey,
+}
+
+impl LockDescriptor {
+    pub fn witness_script(&self) -> Script {
+        Builder::new()
+            .push_key(&self.combined_key)
+            .push_opcode(opcodes::OP_CHECKSIG)
+            .into_script()
+    }
+
+    pub fn script_pubkey(&self) -> Script {
+        self.witness_script().to_v0_p2wsh()
+    }
+}
+
+/// A BIP68 relative timelock, counted in blocks, as it goes into `nSequence`.
+#[derive(Clone, Copy, Debug)]
+pub struct RelativeTimelock(pub u32);
+
+impl RelativeTimelock {
+    fn to_sequence(self) -> u32 {
+        self.0
+    }
+}
+
+/// Everything about the swap's on-chain leg that the cryptographic rounds in
+/// `alice.rs`/`bob.rs` don't otherwise carry: where the lock output actually
+/// lives, who the redeem/refund/punish outputs pay out to, and the
+/// cancel/punish relative timelocks. Agreed out of band before signing
+/// starts, the same way the lock output's funding is broadcast out of band.
+#[derive(Clone, Debug)]
+pub struct SwapChainParams {
+    pub lock_txid: Txid,
+    pub lock_vout: u32,
+    pub lock_value: u64,
+    pub alice_redeem_address: Address,
+    pub bob_refund_address: Address,
+    pub bob_punish_address: Address,
+    pub cancel_timelock: RelativeTimelock,
+    pub punish_timelock: RelativeTimelock,
+}
+
+fn spend_single_output(
+    txid: Txid,
+    vout: u32,
+    outputs: Vec<TxOut>,
+    sequence: u32,
+) -> Transaction {
+    Transaction {
+        version: 2,
+        lock_time: 0,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(txid, vout),
+            script_sig: Script::new(),
+            sequence,
+            witness: vec![],
+        }],
+        output: outputs,
+    }
+}
+
+/// Spends the lock output straight to `recipient`, with no extra timelock:
+/// the happy-path redeem/refund.
+pub fn tx_redeem_or_refund(lock_txid: Txid, lock_vout: u32, lock_value: u64, recipient: &Address) -> Transaction {
+    spend_single_output(
+        lock_txid,
+        lock_vout,
+        vec![TxOut {
+            value: lock_value,
+            script_pubkey: recipient.script_pubkey(),
+        }],
+        0xFFFF_FFFF,
+    )
+}
+
+/// `TxCancel`: spends the lock output into a fresh output, still keyed to
+/// the same combined public key, after `cancel_timelock` blocks -- the
+/// first step of the abort path for a counterparty who locked funds but
+/// then stalled. Only its timelock differs from the lock output itself;
+/// `sig_beta_punish` (computed by the same combined key as every other
+/// partial signature in this swap) is what lets `tx_punish` later spend it.
+pub fn tx_cancel(
+    lock_txid: Txid,
+    lock_vout: u32,
+    lock_value: u64,
+    cancel_timelock: RelativeTimelock,
+    cancel_output: &LockDescriptor,
+) -> Transaction {
+    spend_single_output(
+        lock_txid,
+        lock_vout,
+        vec![TxOut {
+            value: lock_value,
+            script_pubkey: cancel_output.script_pubkey(),
+        }],
+        cancel_timelock.to_sequence(),
+    )
+}
+
+/// `TxPunish`: spends `TxCancel`'s output to `punished_address` once
+/// `punish_timelock` blocks after `TxCancel` confirms. No other transaction
+/// built here spends `TxCancel`'s output; the refund spends the lock output.
+pub fn tx_punish(
+    cancel_txid: Txid,
+    cancel_value: u64,
+    punish_timelock: RelativeTimelock,
+    punished_address: &Address,
+) -> Transaction {
+    spend_single_output(
+        cancel_txid,
+        0,
+        vec![TxOut {
+            value: cancel_value,
+            script_pubkey: punished_address.script_pubkey(),
+        }],
+        punish_timelock.to_sequence(),
+    )
+}
+
+/// The BIP143 sighash that `Alice4::receive_message`/the Bob counterpart
+/// feed into the 2-party ECDSA signing rounds, replacing the opaque message
+/// hashes `beta_redeem_tx()`/`beta_refund_tx()` stood in for.
+pub fn sighash(tx: &Transaction, lock: &LockDescriptor, lock_value: u64) -> secp256k1::Message {
+    let witness_script = lock.witness_script();
+    let components = SighashComponents::new(tx);
+    let hash = components.sighash_all(&tx.input[0], &witness_script, lock_value);
+    secp256k1::Message::from_slice(&hash[..]).expect("sighash is always 32 bytes")
+}
+
+/// The redeem/refund/cancel/punish transactions for one swap, plus the
+/// sighash each one needs signed under `lock`. Alice and Bob each derive
+/// this independently from their own copy of `chain` and the swap's
+/// combined public key -- nothing here crosses the wire.
+pub struct SwapTransactions {
+    pub redeem: Transaction,
+    pub redeem_sighash: secp256k1::Message,
+    pub refund: Transaction,
+    pub refund_sighash: secp256k1::Message,
+    pub cancel: Transaction,
+    pub cancel_sighash: secp256k1::Message,
+    pub punish: Transaction,
+    pub punish_sighash: secp256k1::Message,
+}
+
+pub fn swap_transactions(chain: &SwapChainParams, lock: &LockDescriptor) -> SwapTransactions {
+    let redeem = tx_redeem_or_refund(chain.lock_txid, chain.lock_vout, chain.lock_value, &chain.alice_redeem_address);
+    let refund = tx_redeem_or_refund(chain.lock_txid, chain.lock_vout, chain.lock_value, &chain.bob_refund_address);
+    let cancel = tx_cancel(chain.lock_txid, chain.lock_vout, chain.lock_value, chain.cancel_timelock, lock);
+    let punish = tx_punish(cancel.txid(), chain.lock_value, chain.punish_timelock, &chain.bob_punish_address);
+
+    let redeem_sighash = sighash(&redeem, lock, chain.lock_value);
+    let refund_sighash = sighash(&refund, lock, chain.lock_value);
+    let cancel_sighash = sighash(&cancel, lock, chain.lock_value);
+    let punish_sighash = sighash(&punish, lock, chain.lock_value);
+
+    SwapTransactions {
+        redeem,
+        redeem_sighash,
+        refund,
+        refund_sighash,
+        cancel,
+        cancel_sighash,
+        punish,
+        punish_sighash,
+    }
+}
+
+/// Lets the state machine poll for confirmation or timelock expiry instead
+/// of assuming both parties stay online to hand each other the next
+/// message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptStatus {
+    Unseen,
+    InMempool,
+    Confirmed { confirmations: u32 },
+}
+
+impl ScriptStatus {
+    pub fn has_confirmations(self, target: u32) -> bool {
+        matches!(self, ScriptStatus::Confirmed { confirmations } if confirmations >= target)
+    }
+}
+
+/// A source of truth for what's confirmed on-chain, abstracting over
+/// whatever node/indexer backs it.
+pub trait ChainWatcher {
+    fn status_of(&self, txid: Txid) -> ScriptStatus;
+}
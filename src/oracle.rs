@@ -0,0 +1,263 @@
+//! Oracle-conditioned adaptor points for numeric-outcome DLCs, generalizing
+//! the fixed single adaptor point `Y` that `Bob7::y` recovers in the plain
+//! swap into one contingent on an oracle's attestation of a numeric event.
+//!
+//! The oracle commits in advance to one Schnorr nonce point per base-2
+//! digit of the outcome (an [`OracleAnnouncement`]); once the event
+//! happens it reveals one signature scalar per digit. A contract that pays
+//! out over an outcome interval `[a, b]` is split into the minimal set of
+//! base-2 digit-prefix groupings whose union is `[a, b]`
+//! ([`decompose_interval`]) -- one contract-execution transaction (CET) per
+//! grouping, each adaptor-locked ([`ecdsa::PreSignature::encrypt`]) to the
+//! point the oracle's revealed digits sum to for that prefix
+//! ([`contract_execution_transactions`]). Only the digits the oracle has
+//! revealed matching a CET's fixed prefix let `PreSignature::recover` yield
+//! the oracle's secret for that CET -- any other attested outcome leaves
+//! the other CETs' adaptor points un-openable.
+
+use crate::ecdsa::PreSignature;
+use curv::{
+    arithmetic::traits::Converter,
+    elliptic::curves::traits::{ECPoint, ECScalar},
+    BigInt, FE, GE,
+};
+use merlin::Transcript;
+use secp256k1::Message;
+
+/// What the oracle publishes before the event: its public key and one
+/// Schnorr nonce point per digit, most-significant digit first.
+pub struct OracleAnnouncement {
+    pub public_key: GE,
+    pub nonce_points: Vec<GE>,
+}
+
+/// The Schnorr challenge `e = H(R_i || digit_index || digit)` a verifier
+/// recomputes to turn the oracle's public commitment to digit `digit_index`
+/// into the point it will reveal the discrete log of once it attests
+/// `digit` there.
+fn digit_challenge(nonce_point: &GE, digit_index: usize, digit: u8) -> FE {
+    let mut transcript = Transcript::new(b"ss-ecdsa-poc/oracle/1.0");
+    transcript.append_message(b"nonce-point", &nonce_point.get_element().serialize()[..]);
+    transcript.append_message(b"digit-index", &(digit_index as u64).to_be_bytes());
+    transcript.append_message(b"digit", &[digit]);
+
+    let mut challenge = [0u8; 32];
+    transcript.challenge_bytes(b"challenge", &mut challenge);
+    ECScalar::from(&BigInt::from(&challenge[..]))
+}
+
+/// The point `R_i + e·X` whose discrete log the oracle reveals iff digit
+/// `digit_index` of the attested outcome is `digit` -- the per-digit analogue
+/// of `s·G = R + e·X` for a Schnorr signature the oracle hasn't signed yet.
+fn digit_attestation_point(announcement: &OracleAnnouncement, digit_index: usize, digit: u8) -> GE {
+    let e = digit_challenge(&announcement.nonce_points[digit_index], digit_index, digit);
+    announcement.nonce_points[digit_index] + announcement.public_key * e
+}
+
+/// One base-2 digit-prefix grouping of an outcome interval: `digits` fixes
+/// the top `digits.len()` digits (most-significant first); every outcome
+/// whose attested digits agree with `digits` on that prefix falls in this
+/// grouping, regardless of the remaining, unfixed digits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigitPrefix {
+    pub digits: Vec<u8>,
+}
+
+/// Splits `[a, b]` (inclusive, over `num_digits` base-2 digits) into the
+/// minimal set of [`DigitPrefix`] groupings whose union is exactly `[a, b]`.
+/// Handles `a`/`b` that aren't power-of-two aligned by using a partial
+/// prefix at either end, same as the classic dyadic-interval decomposition
+/// used to aggregate IP ranges into minimal CIDR blocks.
+pub fn decompose_interval(a: u64, b: u64, num_digits: u32) -> Vec<DigitPrefix> {
+    assert!(a <= b, "empty interval");
+    assert!(num_digits < 64, "num_digits must fit a u64 outcome space");
+    assert!(b < (1u64 << num_digits), "b out of range for num_digits");
+
+    let mut groups = Vec::new();
+    let mut lo = a;
+    loop {
+        let remaining = b - lo + 1;
+
+        // The largest power-of-two-aligned block starting at `lo`.
+        let alignment = if lo == 0 {
+            1u64 << num_digits
+        } else {
+            1u64 << lo.trailing_zeros().min(num_digits)
+        };
+        let mut size = alignment;
+        while size > remaining {
+            size /= 2;
+        }
+
+        let free_digits = size.trailing_zeros();
+        let prefix_len = num_digits - free_digits;
+        let prefix_value = lo >> free_digits;
+        groups.push(DigitPrefix {
+            digits: to_digits(prefix_value, prefix_len),
+        });
+
+        // Stop once this group already reaches exactly to `b`.
+        if size == remaining {
+            break;
+        }
+        lo += size;
+    }
+    groups
+}
+
+fn to_digits(value: u64, len: u32) -> Vec<u8> {
+    (0..len)
+        .rev()
+        .map(|i| ((value >> i) & 1) as u8)
+        .collect()
+}
+
+/// A single contract-execution transaction: adaptor-locked to the point the
+/// oracle's digit signatures sum to for `prefix`, or not locked at all when
+/// `prefix` is empty (the whole outcome space, so there's no condition to
+/// wait on).
+pub struct ContractExecutionTx {
+    pub prefix: DigitPrefix,
+    pub outcome_point: Option<GE>,
+    pub pre_signature: PreSignature,
+}
+
+/// Builds one [`ContractExecutionTx`] per grouping in
+/// [`decompose_interval`]'s output, each a [`PreSignature`] for `msg` signed
+/// with `signing_key`/`nonce` and adaptor-encrypted to that grouping's
+/// outcome point. Combined with the oracle's revealed digit signatures for
+/// whichever outcome actually happens, `PreSignature::recover` on the
+/// matching CET (and only that one) yields the sum of those digits'
+/// discrete logs.
+pub fn contract_execution_transactions(
+    announcement: &OracleAnnouncement,
+    a: u64,
+    b: u64,
+    num_digits: u32,
+    signing_key: &FE,
+    nonce: &FE,
+    msg: &Message,
+) -> Vec<ContractExecutionTx> {
+    decompose_interval(a, b, num_digits)
+        .into_iter()
+        .map(|prefix| {
+            let outcome_point = outcome_point(announcement, &prefix);
+            let encryption_point = outcome_point.unwrap_or_else(GE::generator);
+            let pre_signature = PreSignature::encrypt(signing_key, nonce, &encryption_point, msg);
+
+            ContractExecutionTx {
+                prefix,
+                outcome_point,
+                pre_signature,
+            }
+        })
+        .collect()
+}
+
+/// `Σ digit_attestation_point(digit_index, digit)` over `prefix`'s fixed
+/// digits, or `None` if `prefix` fixes nothing (the CET covering the entire
+/// outcome space needs no oracle condition at all).
+fn outcome_point(announcement: &OracleAnnouncement, prefix: &DigitPrefix) -> Option<GE> {
+    let mut points = prefix
+        .digits
+        .iter()
+        .enumerate()
+        .map(|(i, &digit)| digit_attestation_point(announcement, i, digit));
+
+    let first = points.next()?;
+    Some(points.fold(first, |acc, point| acc + point))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn covers(groups: &[DigitPrefix], num_digits: u32) -> Vec<u64> {
+        let mut outcomes = Vec::new();
+        for group in groups {
+            let free_digits = num_digits - group.digits.len() as u32;
+            let prefix_value = group
+                .digits
+                .iter()
+                .fold(0u64, |acc, &digit| (acc << 1) | digit as u64);
+            for suffix in 0..(1u64 << free_digits) {
+                outcomes.push((prefix_value << free_digits) | suffix);
+            }
+        }
+        outcomes.sort_unstable();
+        outcomes
+    }
+
+    #[test]
+    fn decomposes_an_unaligned_interval_exactly() {
+        let groups = decompose_interval(3, 13, 4);
+        assert_eq!(covers(&groups, 4), (3..=13).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn single_point_interval_is_one_full_prefix() {
+        let groups = decompose_interval(5, 5, 4);
+        assert_eq!(groups, vec![DigitPrefix { digits: to_digits(5, 4) }]);
+    }
+
+    #[test]
+    fn whole_range_is_the_empty_prefix() {
+        let groups = decompose_interval(0, 15, 4);
+        assert_eq!(groups, vec![DigitPrefix { digits: vec![] }]);
+    }
+
+    #[test]
+    fn whole_range_cet_has_no_outcome_point() {
+        let announcement = OracleAnnouncement {
+            public_key: GE::generator(),
+            nonce_points: vec![GE::generator(); 4],
+        };
+        let a = FE::new_random();
+        let k = FE::new_random();
+        let msg = Message::from_slice(&[7u8; 32]).unwrap();
+
+        let cets = contract_execution_transactions(&announcement, 0, 15, 4, &a, &k, &msg);
+        assert_eq!(cets.len(), 1);
+        assert!(cets[0].outcome_point.is_none());
+    }
+
+    #[test]
+    fn recover_succeeds_once_the_oracle_attests_the_matching_outcome() {
+        let oracle_key = FE::new_random();
+        let digit_nonces: Vec<FE> = (0..4).map(|_| FE::new_random()).collect();
+        let announcement = OracleAnnouncement {
+            public_key: GE::generator() * oracle_key,
+            nonce_points: digit_nonces.iter().map(|r| GE::generator() * r).collect(),
+        };
+
+        let signing_key = FE::new_random();
+        let nonce = FE::new_random();
+        let msg = Message::from_slice(&[9u8; 32]).unwrap();
+
+        let cets =
+            contract_execution_transactions(&announcement, 4, 4, 4, &signing_key, &nonce, &msg);
+        let cet = &cets[0];
+        let encryption_point = cet.outcome_point.unwrap();
+
+        // The oracle attests outcome 4 == 0b0100, revealing each digit's
+        // Schnorr scalar s_i = r_i + e_i * oracle_key -- their sum is the
+        // discrete log of `encryption_point`.
+        let attested_digits = to_digits(4, 4);
+        let oracle_secret = digit_nonces
+            .iter()
+            .zip(&attested_digits)
+            .enumerate()
+            .map(|(i, (r_i, &digit))| {
+                let e = digit_challenge(&(GE::generator() * r_i), i, digit);
+                *r_i + e * oracle_key
+            })
+            .fold(FE::zero(), |acc, s_i| acc + s_i);
+
+        let signature = cet.pre_signature.decrypt(oracle_secret);
+        let recovered = cet
+            .pre_signature
+            .recover(&encryption_point, &signature)
+            .expect("recover should succeed once the matching outcome is attested");
+        assert_eq!(recovered.to_big_int(), oracle_secret.to_big_int());
+    }
+}
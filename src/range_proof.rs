@@ -0,0 +1,398 @@
+//! A Paillier range proof driven entirely by the same
+//! `merlin::Transcript`/`SSEcdsaTranscript` infrastructure the rest of the
+//! crate uses, replacing the commented-out call to `zk_paillier`'s
+//! interactive range proof in `Alice1::receive_message` ("STOP THE RANGE
+//! PROOF FOR NOW WHICH FAILS NON-DETERMINISTICALLY"). All challenge bits
+//! come from `transcript.challenge_bytes` rather than a separate RNG, which
+//! is the likely cause of that non-determinism, and binding the proof to the
+//! keygen transcript stops it being replayed across sessions.
+//!
+//! This is a cut-and-choose proof that `ciphertext = Enc_pk(x, randomness)`
+//! encrypts some `x` in `[0, slack_bound(q) + q)`, where `slack_bound(q) = q *
+//! 2^40`, **not** the tight `[0, q)` -- the masks need slack that large to
+//! statistically hide `x` in the `Combined` branch below, and shrinking it
+//! to `q` itself would leak `x`'s high bits. What the PDL round actually
+//! needs is that `x` can't wrap the Paillier modulus during the
+//! homomorphic arithmetic that follows (`party_two::PaillierPublic`'s `N`
+//! is a >=2048-bit RSA modulus, versus `q * 2^40` at under 300 bits for
+//! secp256k1's `q`), so this looser bound is sufficient: the prover commits
+//! to [`SECURITY_PARAMETER`] masking ciphertexts `Enc(w_i)`, and for each
+//! one the transcript deterministically decides whether the verifier gets
+//! to see `w_i` alone (bounding it by `slack_bound(q)`) or `w_i` combined
+//! with the real `x` (bounding their sum by `slack_bound(q) + q`). A
+//! cheating prover whose `x` is larger than `slack_bound(q) + q` has to
+//! guess every coin correctly to avoid being caught in either branch --
+//! with [`SECURITY_PARAMETER`] rounds chosen high enough that grinding for
+//! an all-favourable transcript (resampling masks until the derived
+//! challenge happens to match) is infeasible. The cut-and-choose bookkeeping
+//! itself (round openings, challenge derivation) lives in
+//! [`crate::cut_and_choose`], shared with [`crate::escrow`]'s verifiable
+//! encryption, which layers a matching group equation onto the same
+//! scaffolding.
+
+use crate::cut_and_choose::{derive_challenge_bits, RoundOpening, SECURITY_PARAMETER};
+use curv::{
+    arithmetic::traits::{Modulo, Samplable},
+    BigInt,
+};
+use merlin::Transcript;
+use paillier::{
+    traits::EncryptWithChosenRandomness, EncryptionKey, Paillier, Randomness, RawPlaintext,
+};
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct RangeProof {
+    #[serde(with = "crate::serde_curv::bigint_vec")]
+    masks: Vec<BigInt>,
+    openings: Vec<RoundOpening>,
+}
+
+/// Proves `ciphertext == Enc_pk(x, randomness)` encrypts `x` in
+/// `[0, slack_bound(q) + q)`, bound to `transcript` (typically the same
+/// transcript the surrounding keygen round is proving its sigma statements
+/// against).
+pub fn prove(
+    transcript: &mut Transcript,
+    label: &'static [u8],
+    ek: &EncryptionKey,
+    ciphertext: &BigInt,
+    randomness: &BigInt,
+    x: &BigInt,
+    q: &BigInt,
+) -> RangeProof {
+    prove_with_rounds(
+        SECURITY_PARAMETER,
+        transcript,
+        label,
+        ek,
+        ciphertext,
+        randomness,
+        x,
+        q,
+    )
+}
+
+fn prove_with_rounds(
+    rounds: usize,
+    transcript: &mut Transcript,
+    label: &'static [u8],
+    ek: &EncryptionKey,
+    ciphertext: &BigInt,
+    randomness: &BigInt,
+    x: &BigInt,
+    q: &BigInt,
+) -> RangeProof {
+    transcript.append_message(b"ss-ecdsa-poc/range-proof/1.0", label);
+    transcript.append_message(b"ciphertext", &BigInt::to_vec(ciphertext));
+
+    let slack_bound = slack_bound(q);
+    let nn = &ek.n * &ek.n;
+
+    let mut masks = Vec::with_capacity(rounds);
+    let mut ws = Vec::with_capacity(rounds);
+    let mut rs = Vec::with_capacity(rounds);
+
+    for _ in 0..rounds {
+        let w = BigInt::sample_below(&slack_bound);
+        let r = BigInt::sample_below(&ek.n);
+        let c = Paillier::encrypt_with_chosen_randomness(
+            ek,
+            RawPlaintext::from(w.clone()),
+            &Randomness(r.clone()),
+        );
+
+        transcript.append_message(b"mask", &BigInt::to_vec(&c.0));
+        masks.push(c.0.into_owned());
+        ws.push(w);
+        rs.push(r);
+    }
+
+    let challenge_bits = derive_challenge_bits(transcript, label, rounds);
+
+    let openings = challenge_bits
+        .into_iter()
+        .enumerate()
+        .map(|(i, open_combined)| {
+            if open_combined {
+                RoundOpening::Combined {
+                    z: &ws[i] + x,
+                    r: BigInt::mod_mul(&rs[i], randomness, &nn),
+                }
+            } else {
+                RoundOpening::Mask {
+                    w: ws[i].clone(),
+                    r: rs[i].clone(),
+                }
+            }
+        })
+        .collect();
+
+    RangeProof { masks, openings }
+}
+
+/// Verifies a [`RangeProof`] produced by [`prove`], recomputing every
+/// challenge bit from `transcript` rather than trusting the proof to report
+/// it.
+pub fn verify(
+    transcript: &mut Transcript,
+    label: &'static [u8],
+    ek: &EncryptionKey,
+    ciphertext: &BigInt,
+    q: &BigInt,
+    proof: &RangeProof,
+) -> bool {
+    verify_with_rounds(
+        SECURITY_PARAMETER,
+        transcript,
+        label,
+        ek,
+        ciphertext,
+        q,
+        proof,
+    )
+}
+
+fn verify_with_rounds(
+    rounds: usize,
+    transcript: &mut Transcript,
+    label: &'static [u8],
+    ek: &EncryptionKey,
+    ciphertext: &BigInt,
+    q: &BigInt,
+    proof: &RangeProof,
+) -> bool {
+    if proof.masks.len() != rounds || proof.openings.len() != rounds {
+        return false;
+    }
+
+    transcript.append_message(b"ss-ecdsa-poc/range-proof/1.0", label);
+    transcript.append_message(b"ciphertext", &BigInt::to_vec(ciphertext));
+    for mask in &proof.masks {
+        transcript.append_message(b"mask", &BigInt::to_vec(mask));
+    }
+
+    let challenge_bits = derive_challenge_bits(transcript, label, rounds);
+    let slack_bound = slack_bound(q);
+    let nn = &ek.n * &ek.n;
+
+    for ((mask, opening), open_combined) in
+        proof.masks.iter().zip(&proof.openings).zip(challenge_bits)
+    {
+        let ok = match (opening, open_combined) {
+            (RoundOpening::Mask { w, r }, false) => {
+                w < &slack_bound
+                    && Paillier::encrypt_with_chosen_randomness(
+                        ek,
+                        RawPlaintext::from(w.clone()),
+                        &Randomness(r.clone()),
+                    )
+                    .0
+                    .into_owned()
+                        == *mask
+            }
+            (RoundOpening::Combined { z, r }, true) => {
+                z < &(&slack_bound + q)
+                    && Paillier::encrypt_with_chosen_randomness(
+                        ek,
+                        RawPlaintext::from(z.clone()),
+                        &Randomness(r.clone()),
+                    )
+                    .0
+                    .into_owned()
+                        == BigInt::mod_mul(mask, ciphertext, &nn)
+            }
+            _ => false,
+        };
+
+        if !ok {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn slack_bound(q: &BigInt) -> BigInt {
+    // Statistical hiding slack for the masks, sized so that it swamps `q`
+    // (keeping the Combined branch's `w + x` from leaking `x`'s value) while
+    // staying far below the Paillier modulus `ek.n` (so a value this large
+    // still can't wrap `N` during the homomorphic arithmetic PDL performs on
+    // it) -- see the module doc comment.
+    q * &BigInt::from(1u64 << 40)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paillier::traits::KeyGeneration;
+
+    #[test]
+    fn honest_proof_verifies() {
+        let (ek, _dk) = Paillier::keypair().keys();
+        let q = BigInt::from(123_456_789u64);
+        let x = BigInt::from(42u64);
+        let randomness = BigInt::sample_below(&ek.n);
+        let ciphertext = Paillier::encrypt_with_chosen_randomness(
+            &ek,
+            RawPlaintext::from(x.clone()),
+            &Randomness(randomness.clone()),
+        )
+        .0
+        .into_owned();
+
+        let mut prover_transcript = Transcript::new(b"range_proof_test");
+        let proof = prove(
+            &mut prover_transcript,
+            b"x",
+            &ek,
+            &ciphertext,
+            &randomness,
+            &x,
+            &q,
+        );
+
+        let mut verifier_transcript = Transcript::new(b"range_proof_test");
+        assert!(verify(
+            &mut verifier_transcript,
+            b"x",
+            &ek,
+            &ciphertext,
+            &q,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn tampered_ciphertext_is_rejected() {
+        let (ek, _dk) = Paillier::keypair().keys();
+        let q = BigInt::from(123_456_789u64);
+        let x = BigInt::from(42u64);
+        let randomness = BigInt::sample_below(&ek.n);
+        let ciphertext = Paillier::encrypt_with_chosen_randomness(
+            &ek,
+            RawPlaintext::from(x.clone()),
+            &Randomness(randomness.clone()),
+        )
+        .0
+        .into_owned();
+
+        let mut prover_transcript = Transcript::new(b"range_proof_tamper_test");
+        let proof = prove(
+            &mut prover_transcript,
+            b"x",
+            &ek,
+            &ciphertext,
+            &randomness,
+            &x,
+            &q,
+        );
+
+        let tampered_ciphertext = BigInt::mod_add(&ciphertext, &BigInt::one(), &(&ek.n * &ek.n));
+
+        let mut verifier_transcript = Transcript::new(b"range_proof_tamper_test");
+        assert!(!verify(
+            &mut verifier_transcript,
+            b"x",
+            &ek,
+            &tampered_ciphertext,
+            &q,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn out_of_slack_bound_x_is_rejected() {
+        let (ek, _dk) = Paillier::keypair().keys();
+        let q = BigInt::from(123_456_789u64);
+        // Just past `slack_bound(q) + q`, so every Combined opening overflows
+        // the bound and gets caught (Mask openings don't involve `x`).
+        let x = slack_bound(&q) + &q + BigInt::from(1u64);
+        let randomness = BigInt::sample_below(&ek.n);
+        let ciphertext = Paillier::encrypt_with_chosen_randomness(
+            &ek,
+            RawPlaintext::from(x.clone()),
+            &Randomness(randomness.clone()),
+        )
+        .0
+        .into_owned();
+
+        let mut prover_transcript = Transcript::new(b"range_proof_out_of_range_test");
+        let proof = prove(
+            &mut prover_transcript,
+            b"x",
+            &ek,
+            &ciphertext,
+            &randomness,
+            &x,
+            &q,
+        );
+
+        let mut verifier_transcript = Transcript::new(b"range_proof_out_of_range_test");
+        assert!(!verify(
+            &mut verifier_transcript,
+            b"x",
+            &ek,
+            &ciphertext,
+            &q,
+            &proof
+        ));
+    }
+
+    /// At a low round count, a cheating prover who keeps resampling its masks
+    /// until every round's transcript-derived coin lands in its favour can
+    /// forge an accepting proof for an out-of-range `x` -- exactly the attack
+    /// [`SECURITY_PARAMETER`] = 128 is sized to put out of reach (this test
+    /// uses 8 rounds, where ~2^8 attempts are expected to find one; it allows
+    /// 2^12 so that missing a forgery is a ~e^-16 event rather than ~37%).
+    #[test]
+    fn grinding_forges_an_out_of_range_proof_at_low_rounds() {
+        let rounds = 8;
+        let max_attempts_log2 = rounds + 4;
+        let (ek, _dk) = Paillier::keypair().keys();
+        let q = BigInt::from(123_456_789u64);
+        let x = slack_bound(&q) + &q + BigInt::from(1u64);
+
+        let forged = (0..1 << max_attempts_log2).find_map(|attempt: u32| {
+            let randomness = BigInt::sample_below(&ek.n);
+            let ciphertext = Paillier::encrypt_with_chosen_randomness(
+                &ek,
+                RawPlaintext::from(x.clone()),
+                &Randomness(randomness.clone()),
+            )
+            .0
+            .into_owned();
+
+            let mut prover_transcript =
+                Transcript::new(format!("grinding_attack_test/{}", attempt).as_bytes());
+            let proof = prove_with_rounds(
+                rounds,
+                &mut prover_transcript,
+                b"x",
+                &ek,
+                &ciphertext,
+                &randomness,
+                &x,
+                &q,
+            );
+
+            let mut verifier_transcript =
+                Transcript::new(format!("grinding_attack_test/{}", attempt).as_bytes());
+            verify_with_rounds(
+                rounds,
+                &mut verifier_transcript,
+                b"x",
+                &ek,
+                &ciphertext,
+                &q,
+                &proof,
+            )
+            .then(|| ())
+        });
+
+        assert!(
+            forged.is_some(),
+            "expected grinding to find an accepting out-of-range proof within 2^{} attempts",
+            max_attempts_log2
+        );
+    }
+}
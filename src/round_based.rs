@@ -0,0 +1,284 @@
+//! Drives the hand-threaded `Alice1..Alice4`/`Bob1..Bob7` typestate chains
+//! (`alice.rs`/`bob.rs`) from behind a uniform, `round_based`-style
+//! interface: feed in whatever message just arrived over the wire via
+//! [`StateMachine::handle_incoming`], drain anything ready to send via
+//! [`StateMachine::proceed`], and read the final result once
+//! [`StateMachine::is_finished`]. This lets an external transport/I/O loop
+//! drive either side without knowing about `Alice1`/`Bob3`/etc. by name.
+//!
+//! Only the keygen/PDL/sign rounds are modeled here -- `cancel`/`punish` are
+//! abort paths triggered by on-chain timelock events, not further protocol
+//! messages, so `Bob5::cancel`/`observe_cancel_confirmed`/`Bob6::punish`
+//! stay direct calls against the finished [`BobMachine`]'s output.
+
+use crate::{
+    alice::{Alice1, Alice2, Alice3, Alice4},
+    bob::{Bob1, Bob2, Bob3, Bob4, Bob5},
+    ecdsa::Signature,
+    messages::{
+        BlockchainMsg, KeyGenMsg1, KeyGenMsg2, KeyGenMsg3, PdlMsg1, PdlMsg2, PdlMsg3, PdlMsg4,
+        SignMsg1, SignMsg2,
+    },
+    onchain::{ChainWatcher, SwapChainParams},
+};
+use bitcoin::Txid;
+use merlin::Transcript;
+
+/// A single round-driven protocol participant.
+pub trait StateMachine {
+    /// The message type(s) this participant can receive, one round at a time.
+    type In;
+    /// The message type(s) this participant can send.
+    type Out;
+    /// What the protocol produces once finished.
+    type Output;
+    type Err;
+
+    /// Feed one incoming message for the current round. Returns `Err` if
+    /// `msg` isn't the variant the current round expects, or if the
+    /// underlying cryptographic check it triggers fails.
+    fn handle_incoming(&mut self, msg: Self::In) -> Result<(), Self::Err>;
+
+    /// Takes the outgoing message produced by the last [`Self::handle_incoming`]
+    /// call, if any is still pending.
+    fn proceed(&mut self) -> Result<Option<Self::Out>, Self::Err>;
+
+    /// Whether the protocol has produced its final output.
+    fn is_finished(&self) -> bool;
+
+    /// Takes the final output. Returns `None` before completion, or if
+    /// already taken.
+    fn pick_output(&mut self) -> Option<Result<Self::Output, Self::Err>>;
+}
+
+pub enum AliceIn {
+    KeyGenMsg1(KeyGenMsg1),
+    KeyGenMsg3(KeyGenMsg3),
+    PdlMsg2(PdlMsg2),
+    PdlMsg4(PdlMsg4),
+    SignMsg2(SignMsg2),
+}
+
+pub enum AliceOut {
+    KeyGenMsg2(KeyGenMsg2),
+    PdlMsg1(PdlMsg1),
+    PdlMsg3(PdlMsg3),
+    SignMsg1(SignMsg1),
+    BlockchainMsg(BlockchainMsg),
+}
+
+enum AliceState {
+    AwaitingKeyGenMsg1,
+    AwaitingKeyGenMsg3(Alice1),
+    AwaitingPdlMsg2(Alice2),
+    AwaitingPdlMsg4(Alice3),
+    AwaitingSignMsg2(Alice4),
+    Finished,
+}
+
+/// Drives Alice's side of keygen + PDL + sign as a [`StateMachine`]. Alice's
+/// own output is nothing more than "the protocol completed" -- she already
+/// knows `y`, unlike Bob, who learns it from the eventual on-chain redeem.
+pub struct AliceMachine {
+    transcript: Transcript,
+    chain: SwapChainParams,
+    state: AliceState,
+    pending_out: Option<AliceOut>,
+    output: Option<Result<(), ()>>,
+}
+
+impl AliceMachine {
+    pub fn new(transcript: Transcript, chain: SwapChainParams) -> Self {
+        AliceMachine {
+            transcript,
+            chain,
+            state: AliceState::AwaitingKeyGenMsg1,
+            pending_out: None,
+            output: None,
+        }
+    }
+}
+
+impl StateMachine for AliceMachine {
+    type In = AliceIn;
+    type Out = AliceOut;
+    type Output = ();
+    type Err = ();
+
+    fn handle_incoming(&mut self, msg: AliceIn) -> Result<(), ()> {
+        let state = std::mem::replace(&mut self.state, AliceState::Finished);
+        let (next_state, out) = match (state, msg) {
+            (AliceState::AwaitingKeyGenMsg1, AliceIn::KeyGenMsg1(m)) => {
+                let (alice1, out) = Alice1::new(&mut self.transcript, m);
+                (
+                    AliceState::AwaitingKeyGenMsg3(alice1),
+                    AliceOut::KeyGenMsg2(out),
+                )
+            }
+            (AliceState::AwaitingKeyGenMsg3(alice1), AliceIn::KeyGenMsg3(m)) => {
+                let (alice2, out) = alice1.receive_message(m)?;
+                (AliceState::AwaitingPdlMsg2(alice2), AliceOut::PdlMsg1(out))
+            }
+            (AliceState::AwaitingPdlMsg2(alice2), AliceIn::PdlMsg2(m)) => {
+                let (alice3, out) = alice2.receive_message(m);
+                (AliceState::AwaitingPdlMsg4(alice3), AliceOut::PdlMsg3(out))
+            }
+            (AliceState::AwaitingPdlMsg4(alice3), AliceIn::PdlMsg4(m)) => {
+                let (alice4, out) = alice3.receive_message(m, self.chain.clone())?;
+                (AliceState::AwaitingSignMsg2(alice4), AliceOut::SignMsg1(out))
+            }
+            (AliceState::AwaitingSignMsg2(alice4), AliceIn::SignMsg2(m)) => {
+                let ((), out) = alice4.receive_message(m)?;
+                self.output = Some(Ok(()));
+                (AliceState::Finished, AliceOut::BlockchainMsg(out))
+            }
+            (state, _) => {
+                self.state = state;
+                return Err(());
+            }
+        };
+        self.state = next_state;
+        self.pending_out = Some(out);
+        Ok(())
+    }
+
+    fn proceed(&mut self) -> Result<Option<AliceOut>, ()> {
+        Ok(self.pending_out.take())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.output.is_some()
+    }
+
+    fn pick_output(&mut self) -> Option<Result<(), ()>> {
+        self.output.take()
+    }
+}
+
+pub enum BobIn {
+    KeyGenMsg2(KeyGenMsg2),
+    PdlMsg1(PdlMsg1),
+    PdlMsg3(PdlMsg3),
+    SignMsg1(SignMsg1),
+    BlockchainMsg(BlockchainMsg),
+}
+
+pub enum BobOut {
+    KeyGenMsg1(KeyGenMsg1),
+    KeyGenMsg3(KeyGenMsg3),
+    PdlMsg2(PdlMsg2),
+    PdlMsg4(PdlMsg4),
+    SignMsg2(SignMsg2),
+}
+
+enum BobState {
+    AwaitingKeyGenMsg2(Bob1),
+    AwaitingPdlMsg1(Bob2),
+    AwaitingPdlMsg3(Bob3),
+    AwaitingSignMsg1(Bob4),
+    AwaitingBlockchainMsg(Bob5),
+    Finished,
+}
+
+/// Drives Bob's side of keygen + PDL + sign as a [`StateMachine`]. Unlike
+/// Alice, Bob is the one who sends the first message (`KeyGenMsg1`), so
+/// [`BobMachine::new`] runs that round eagerly and queues it as the first
+/// `proceed()`-able output rather than waiting on an initial
+/// `handle_incoming`.
+pub struct BobMachine {
+    transcript: Transcript,
+    chain: SwapChainParams,
+    state: BobState,
+    pending_out: Option<BobOut>,
+    output: Option<Result<curv::FE, ()>>,
+}
+
+impl BobMachine {
+    pub fn new(mut transcript: Transcript, chain: SwapChainParams) -> Self {
+        let (bob1, out) = Bob1::new(&mut transcript);
+        BobMachine {
+            transcript,
+            chain,
+            state: BobState::AwaitingKeyGenMsg2(bob1),
+            pending_out: Some(BobOut::KeyGenMsg1(out)),
+            output: None,
+        }
+    }
+
+    /// Exposes `Bob5::cancel` once `watcher` shows the lock output has sat
+    /// on-chain for `confirmations_before_stalled` confirmations without
+    /// Alice sending `BlockchainMsg` -- a read-only peek at the in-flight
+    /// `Bob5`, not a [`StateMachine`] transition, since (per this module's
+    /// doc comment) `cancel`/`punish` are on-chain timelock events rather
+    /// than further protocol messages.
+    pub fn cancel_if_stalled(
+        &self,
+        watcher: &dyn ChainWatcher,
+        lock_txid: Txid,
+        confirmations_before_stalled: u32,
+    ) -> Option<&Signature> {
+        match &self.state {
+            BobState::AwaitingBlockchainMsg(bob5)
+                if watcher
+                    .status_of(lock_txid)
+                    .has_confirmations(confirmations_before_stalled) =>
+            {
+                Some(bob5.cancel())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl StateMachine for BobMachine {
+    type In = BobIn;
+    type Out = BobOut;
+    type Output = curv::FE;
+    type Err = ();
+
+    fn handle_incoming(&mut self, msg: BobIn) -> Result<(), ()> {
+        let state = std::mem::replace(&mut self.state, BobState::Finished);
+        let (next_state, out) = match (state, msg) {
+            (BobState::AwaitingKeyGenMsg2(bob1), BobIn::KeyGenMsg2(m)) => {
+                let (bob2, out) = bob1.receive_message(&mut self.transcript, m)?;
+                (BobState::AwaitingPdlMsg1(bob2), Some(BobOut::KeyGenMsg3(out)))
+            }
+            (BobState::AwaitingPdlMsg1(bob2), BobIn::PdlMsg1(m)) => {
+                let (bob3, out) = bob2.receive_message(m)?;
+                (BobState::AwaitingPdlMsg3(bob3), Some(BobOut::PdlMsg2(out)))
+            }
+            (BobState::AwaitingPdlMsg3(bob3), BobIn::PdlMsg3(m)) => {
+                let (bob4, out) = bob3.receive_message(m)?;
+                (BobState::AwaitingSignMsg1(bob4), Some(BobOut::PdlMsg4(out)))
+            }
+            (BobState::AwaitingSignMsg1(bob4), BobIn::SignMsg1(m)) => {
+                let (bob5, out) = bob4.receive_message(m, self.chain.clone())?;
+                (BobState::AwaitingBlockchainMsg(bob5), Some(BobOut::SignMsg2(out)))
+            }
+            (BobState::AwaitingBlockchainMsg(bob5), BobIn::BlockchainMsg(m)) => {
+                let (bob7, ()) = bob5.receive_message(m)?;
+                self.output = Some(Ok(bob7.y));
+                (BobState::Finished, None)
+            }
+            (state, _) => {
+                self.state = state;
+                return Err(());
+            }
+        };
+        self.state = next_state;
+        self.pending_out = out;
+        Ok(())
+    }
+
+    fn proceed(&mut self) -> Result<Option<BobOut>, ()> {
+        Ok(self.pending_out.take())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.output.is_some()
+    }
+
+    fn pick_output(&mut self) -> Option<Result<curv::FE, ()>> {
+        self.output.take()
+    }
+}
@@ -0,0 +1,90 @@
+//! `serde` support for the `curv` point/scalar/bigint types used throughout
+//! `messages.rs`, via the usual `#[serde(with = "...")]` escape hatch for
+//! foreign types the orphan rules won't let us `impl Serialize`/`Deserialize`
+//! for directly. Points and scalars use the same encodings
+//! `nizk_sigma_proof.rs`'s `TranscriptWrite`/`TranscriptRead` use on the
+//! wire (SEC1 compressed points, raw 32-byte scalars); bigints are
+//! big-endian bytes.
+
+use curv::{
+    elliptic::curves::traits::{ECPoint, ECScalar},
+    BigInt, FE, GE,
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+pub mod point {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(point: &GE, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&point.get_element().serialize()[..])
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<GE, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        ECPoint::from_bytes(&bytes[1..]).map_err(|_| D::Error::custom("invalid point encoding"))
+    }
+}
+
+pub mod scalar {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(scalar: &FE, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&scalar.get_element()[..])
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<FE, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Ok(ECScalar::from(&BigInt::from(&bytes[..])))
+    }
+}
+
+pub mod bigint {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(n: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&BigInt::to_vec(n))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Ok(BigInt::from(&bytes[..]))
+    }
+}
+
+pub mod point_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(points: &[GE], serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes: Vec<Vec<u8>> = points
+            .iter()
+            .map(|p| p.get_element().serialize().to_vec())
+            .collect();
+        bytes.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<GE>, D::Error> {
+        let bytes = Vec::<Vec<u8>>::deserialize(deserializer)?;
+        bytes
+            .into_iter()
+            .map(|b| {
+                ECPoint::from_bytes(&b[1..]).map_err(|_| D::Error::custom("invalid point encoding"))
+            })
+            .collect()
+    }
+}
+
+pub mod bigint_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(ns: &[BigInt], serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes: Vec<Vec<u8>> = ns.iter().map(BigInt::to_vec).collect();
+        bytes.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<BigInt>, D::Error> {
+        let bytes = Vec::<Vec<u8>>::deserialize(deserializer)?;
+        Ok(bytes.into_iter().map(|b| BigInt::from(&b[..])).collect())
+    }
+}